@@ -0,0 +1,139 @@
+use std::{fmt, str::FromStr};
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum AggregateError {
+    #[error("missing prefix: {0}")]
+    MissingPrefix(String),
+
+    #[error("invalid prefix: {0}")]
+    InvalidPrefix(String),
+
+    #[error("missing id: {0}")]
+    MissingId(String),
+}
+
+/// Aggregate identity formatted as `prefix/id`.
+///
+/// The prefix ends at the first `/` and can't contain one, everything after it is the id
+/// verbatim, so ids may contain `/` without escaping.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Aggregate {
+    pub prefix: String,
+    pub id: String,
+}
+
+impl Aggregate {
+    pub fn new(prefix: impl Into<String>, id: impl Into<String>) -> Result<Self, AggregateError> {
+        let prefix = prefix.into();
+        let id = id.into();
+
+        if prefix.is_empty() || prefix.contains('/') {
+            return Err(AggregateError::InvalidPrefix(prefix));
+        }
+
+        if id.is_empty() {
+            return Err(AggregateError::MissingId(format!("{prefix}/")));
+        }
+
+        Ok(Self { prefix, id })
+    }
+}
+
+impl FromStr for Aggregate {
+    type Err = AggregateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, id) = split(s)?;
+
+        Ok(Self {
+            prefix: prefix.to_owned(),
+            id: id.to_owned(),
+        })
+    }
+}
+
+pub(crate) fn split(s: &str) -> Result<(&str, &str), AggregateError> {
+    let Some((prefix, id)) = s.split_once('/') else {
+        return Err(AggregateError::MissingPrefix(s.to_owned()));
+    };
+
+    if prefix.is_empty() {
+        return Err(AggregateError::MissingPrefix(s.to_owned()));
+    }
+
+    if id.is_empty() {
+        return Err(AggregateError::MissingId(s.to_owned()));
+    }
+
+    Ok((prefix, id))
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.prefix, self.id)
+    }
+}
+
+impl From<Aggregate> for String {
+    fn from(val: Aggregate) -> Self {
+        val.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let aggregate = "user/01JDZ7C5S0V3BE2E8X7DQPBHNT"
+            .parse::<Aggregate>()
+            .unwrap();
+
+        assert_eq!(aggregate.prefix, "user");
+        assert_eq!(aggregate.id, "01JDZ7C5S0V3BE2E8X7DQPBHNT");
+        assert_eq!(aggregate.to_string(), "user/01JDZ7C5S0V3BE2E8X7DQPBHNT");
+    }
+
+    #[test]
+    fn id_with_slashes() {
+        let aggregate = "file/docs/2024/report.pdf".parse::<Aggregate>().unwrap();
+
+        assert_eq!(aggregate.prefix, "file");
+        assert_eq!(aggregate.id, "docs/2024/report.pdf");
+
+        let aggregate = Aggregate::new("file", "docs/2024/report.pdf").unwrap();
+
+        assert_eq!(
+            aggregate.to_string().parse::<Aggregate>().unwrap(),
+            aggregate
+        );
+    }
+
+    #[test]
+    fn missing_prefix() {
+        assert_eq!(
+            "01JDZ7C5S0V3BE2E8X7DQPBHNT".parse::<Aggregate>(),
+            Err(AggregateError::MissingPrefix(
+                "01JDZ7C5S0V3BE2E8X7DQPBHNT".to_owned()
+            ))
+        );
+
+        assert_eq!(
+            "/01JDZ7C5S0V3BE2E8X7DQPBHNT".parse::<Aggregate>(),
+            Err(AggregateError::MissingPrefix(
+                "/01JDZ7C5S0V3BE2E8X7DQPBHNT".to_owned()
+            ))
+        );
+
+        assert_eq!(
+            "user/".parse::<Aggregate>(),
+            Err(AggregateError::MissingId("user/".to_owned()))
+        );
+
+        assert_eq!(
+            Aggregate::new("user/admin", "1"),
+            Err(AggregateError::InvalidPrefix("user/admin".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+#[derive(thiserror::Error, Debug)]
+pub enum CodecError {
+    #[error("cbor ser: {0}")]
+    CiboriumSer(#[from] ciborium::ser::Error<std::io::Error>),
+
+    #[error("cbor de: {0}")]
+    CiboriumDe(#[from] ciborium::de::Error<std::io::Error>),
+
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unknown content type: {0}")]
+    UnknownContentType(String),
+
+    #[error("content type mismatch: expected {expected}, found {found}")]
+    ContentTypeMismatch {
+        expected: &'static str,
+        found: String,
+    },
+
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub trait Codec {
+    const CONTENT_TYPE: &'static str;
+
+    fn encode<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>, CodecError>;
+    fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, CodecError>;
+}
+
+pub struct Cbor;
+
+impl Codec for Cbor {
+    const CONTENT_TYPE: &'static str = "application/cbor";
+
+    fn encode<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        let mut encoded = Vec::new();
+        ciborium::into_writer(value, &mut encoded)?;
+
+        Ok(encoded)
+    }
+
+    fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, CodecError> {
+        Ok(ciborium::from_reader(data)?)
+    }
+}
+
+pub struct Json;
+
+impl Codec for Json {
+    const CONTENT_TYPE: &'static str = "application/json";
+
+    fn encode<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, CodecError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
@@ -1,10 +1,12 @@
 use base64::{
     alphabet,
-    engine::{general_purpose, GeneralPurpose},
+    engine::{general_purpose, DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
     Engine,
 };
+use ciborium::Value;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sqlx::{query::QueryAs, Database};
+use ulid::Ulid;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -13,11 +15,110 @@ pub enum Error {
 
     #[error("cbor de: {0}")]
     CiboriumDe(#[from] ciborium::de::Error<std::io::Error>),
+
+    #[error("cbor value: {0}")]
+    CiboriumValue(#[from] ciborium::value::Error),
+
+    #[error("cursor format mismatch: expected one of {expected:?}, found {found:?}")]
+    CursorFormatMismatch {
+        expected: &'static [u8],
+        found: Option<u8>,
+    },
 }
 
+const CURSOR_FORMAT: u8 = 1;
+const COMPACT_CURSOR_FORMAT: u8 = 2;
+const CURSOR_FORMATS: &[u8] = &[CURSOR_FORMAT, COMPACT_CURSOR_FORMAT];
+const ULID_TAG: u64 = 61_937;
+
 #[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
 pub struct Cursor(pub String);
 
+impl Cursor {
+    pub fn encode<C: Serialize>(cursor: &C) -> Result<Self, ciborium::ser::Error<std::io::Error>> {
+        let mut cbor_encoded = vec![CURSOR_FORMAT];
+        ciborium::into_writer(cursor, &mut cbor_encoded)?;
+
+        let engine = GeneralPurpose::new(&alphabet::URL_SAFE, general_purpose::PAD);
+
+        Ok(Self(engine.encode(cbor_encoded)))
+    }
+
+    pub fn encode_compact<C: Serialize>(
+        cursor: &C,
+    ) -> Result<Self, ciborium::ser::Error<std::io::Error>> {
+        let value =
+            Value::serialized(cursor).map_err(|e| ciborium::ser::Error::Value(e.to_string()))?;
+
+        let mut cbor_encoded = vec![COMPACT_CURSOR_FORMAT];
+        ciborium::into_writer(&pack_ulids(value), &mut cbor_encoded)?;
+
+        let engine = GeneralPurpose::new(&alphabet::URL_SAFE, general_purpose::NO_PAD);
+
+        Ok(Self(engine.encode(cbor_encoded)))
+    }
+
+    pub fn decode<C: DeserializeOwned>(&self) -> Result<C, Error> {
+        let engine = GeneralPurpose::new(
+            &alphabet::URL_SAFE,
+            GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+        );
+        let decoded = engine.decode(self)?;
+
+        match decoded.first() {
+            Some(&CURSOR_FORMAT) => Ok(ciborium::from_reader(&decoded[1..])?),
+            Some(&COMPACT_CURSOR_FORMAT) => {
+                let value = ciborium::from_reader::<Value, _>(&decoded[1..])?;
+
+                Ok(unpack_ulids(value).deserialized()?)
+            }
+            found => Err(Error::CursorFormatMismatch {
+                expected: CURSOR_FORMATS,
+                found: found.copied(),
+            }),
+        }
+    }
+}
+
+fn pack_ulids(value: Value) -> Value {
+    match value {
+        Value::Text(text) => match Ulid::from_string(&text) {
+            Ok(ulid) if ulid.to_string() == text => {
+                Value::Tag(ULID_TAG, Box::new(Value::Bytes(ulid.to_bytes().to_vec())))
+            }
+            _ => Value::Text(text),
+        },
+        Value::Array(values) => Value::Array(values.into_iter().map(pack_ulids).collect()),
+        Value::Map(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k, pack_ulids(v)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+fn unpack_ulids(value: Value) -> Value {
+    match value {
+        Value::Tag(ULID_TAG, value) => match *value {
+            Value::Bytes(bytes) => match <[u8; 16]>::try_from(bytes) {
+                Ok(bytes) => Value::Text(Ulid::from_bytes(bytes).to_string()),
+                Err(bytes) => Value::Tag(ULID_TAG, Box::new(Value::Bytes(bytes))),
+            },
+            value => Value::Tag(ULID_TAG, Box::new(value)),
+        },
+        Value::Array(values) => Value::Array(values.into_iter().map(unpack_ulids).collect()),
+        Value::Map(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k, unpack_ulids(v)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
 impl From<String> for Cursor {
     fn from(val: String) -> Self {
         Self(val)
@@ -35,14 +136,11 @@ pub trait ToCursor {
 
     fn serialize_cursor(&self) -> Self::Cursor;
     fn to_cursor(&self) -> Result<Cursor, ciborium::ser::Error<std::io::Error>> {
-        let cursor = self.serialize_cursor();
-
-        let mut cbor_encoded = vec![];
-        ciborium::into_writer(&cursor, &mut cbor_encoded)?;
-
-        let engine = GeneralPurpose::new(&alphabet::URL_SAFE, general_purpose::PAD);
+        Cursor::encode(&self.serialize_cursor())
+    }
 
-        Ok(Cursor(engine.encode(cbor_encoded)))
+    fn to_compact_cursor(&self) -> Result<Cursor, ciborium::ser::Error<std::io::Error>> {
+        Cursor::encode_compact(&self.serialize_cursor())
     }
 }
 
@@ -60,10 +158,6 @@ pub trait BindCursor<'q, DB: Database> {
         value: &Cursor,
         query: QueryAs<'q, DB, O, DB::Arguments<'q>>,
     ) -> Result<QueryAs<'q, DB, O, DB::Arguments<'q>>, Error> {
-        let engine = GeneralPurpose::new(&alphabet::URL_SAFE, general_purpose::PAD);
-        let decoded = engine.decode(value)?;
-        let cursor = ciborium::from_reader(&decoded[..])?;
-
-        Ok(Self::bind_query(cursor, query))
+        Ok(Self::bind_query(value.decode()?, query))
     }
 }
@@ -1,15 +1,43 @@
-use crate::{BindCursor, ToCursor};
-use serde::{Deserialize, Serialize};
-use sqlx::{query::QueryAs, Database, Encode, FromRow, Type};
+use crate::{aggregate, BindCursor, Cbor, Codec, CodecError, Json, SqliteReader, ToCursor};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sqlx::{
+    query::QueryAs, ColumnIndex, Database, Decode, Encode, FromRow, QueryBuilder, Row, SqlitePool,
+    Type,
+};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[derive(thiserror::Error, Debug)]
+pub enum EventError {
+    #[error(transparent)]
+    Codec(#[from] CodecError),
+
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Event {
     pub id: String,
     pub name: String,
     pub aggregate: String,
+    pub stream: String,
     pub version: u16,
+    pub schema_version: u16,
     pub data: Vec<u8>,
     pub metadata: Option<Vec<u8>>,
+    pub content_type: String,
+    pub timestamp: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct EventHeader {
+    pub id: String,
+    pub name: String,
+    pub aggregate: String,
+    pub stream: String,
+    pub version: u16,
+    pub schema_version: u16,
+    pub content_type: String,
     pub timestamp: u32,
 }
 
@@ -20,25 +48,264 @@ pub struct EventCursor {
     pub t: u32,
 }
 
+pub(crate) const EVENT_SOURCE: &str = "(SELECT event.id, event.name, event.aggregate, event.stream, event.version, event.schema_version, COALESCE(event_data.data, event.data) AS data, COALESCE(event_data.metadata, event.metadata) AS metadata, event.content_type, event.timestamp FROM event LEFT JOIN event_data ON event_data.id = event.id) AS event";
+
+pub(crate) const HEADER_COLUMNS: &str =
+    "id, name, aggregate, stream, version, schema_version, content_type, timestamp";
+
 impl Event {
-    pub fn to_data<D: serde::de::DeserializeOwned>(
-        &self,
-    ) -> Result<Option<D>, ciborium::de::Error<std::io::Error>> {
+    pub fn reader<'args>() -> SqliteReader<'args, Event> {
+        SqliteReader::new(format!("SELECT * FROM {EVENT_SOURCE}"))
+    }
+
+    pub async fn read_versions(
+        executor: &SqlitePool,
+        aggregate: impl Into<String>,
+        lo: u16,
+        hi: u16,
+    ) -> Result<Vec<Event>, sqlx::Error> {
+        Self::read_stream_versions(executor, aggregate, "", lo, hi).await
+    }
+
+    pub async fn read_stream_versions(
+        executor: &SqlitePool,
+        aggregate: impl Into<String>,
+        stream: impl Into<String>,
+        lo: u16,
+        hi: u16,
+    ) -> Result<Vec<Event>, sqlx::Error> {
+        sqlx::query_as::<_, Event>(&format!(
+            "SELECT * FROM {EVENT_SOURCE} WHERE aggregate = $1 AND stream = $2 AND version > $3 AND version <= $4 ORDER BY version",
+        ))
+        .bind(aggregate.into())
+        .bind(stream.into())
+        .bind(lo)
+        .bind(hi)
+        .fetch_all(executor)
+        .await
+    }
+
+    pub async fn by_version(
+        executor: &SqlitePool,
+        aggregate: impl Into<String>,
+        version: u16,
+    ) -> Result<Option<Event>, sqlx::Error> {
+        Self::by_stream_version(executor, aggregate, "", version).await
+    }
+
+    pub async fn by_stream_version(
+        executor: &SqlitePool,
+        aggregate: impl Into<String>,
+        stream: impl Into<String>,
+        version: u16,
+    ) -> Result<Option<Event>, sqlx::Error> {
+        sqlx::query_as::<_, Event>(&format!(
+            "SELECT * FROM {EVENT_SOURCE} WHERE aggregate = $1 AND stream = $2 AND version = $3",
+        ))
+        .bind(aggregate.into())
+        .bind(stream.into())
+        .bind(version)
+        .fetch_optional(executor)
+        .await
+    }
+
+    pub async fn latest_of_type<D: DeserializeOwned>(
+        executor: &SqlitePool,
+        aggregate: impl Into<String>,
+    ) -> Result<Option<D>, EventError> {
+        let event = sqlx::query_as::<_, Event>(&format!(
+            "SELECT * FROM {EVENT_SOURCE} WHERE aggregate = $1 AND name = $2 ORDER BY timestamp DESC, version DESC, id DESC LIMIT 1",
+        ))
+        .bind(aggregate.into())
+        .bind(std::any::type_name::<D>())
+        .fetch_optional(executor)
+        .await?;
+
+        match event {
+            Some(event) => Ok(event.to_data()?),
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn read_grouped(
+        executor: &SqlitePool,
+        aggregates: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<HashMap<String, Vec<Event>>, sqlx::Error> {
+        let mut grouped = aggregates
+            .into_iter()
+            .map(|aggregate| (aggregate.into(), vec![]))
+            .collect::<HashMap<_, _>>();
+
+        if grouped.is_empty() {
+            return Ok(grouped);
+        }
+
+        let mut qb =
+            QueryBuilder::new(format!("SELECT * FROM {EVENT_SOURCE} WHERE aggregate IN ("));
+        let mut separated = qb.separated(", ");
+        for aggregate in grouped.keys() {
+            separated.push_bind(aggregate);
+        }
+        separated.push_unseparated(") ORDER BY aggregate, stream, version");
+
+        let events = qb.build_query_as::<Event>().fetch_all(executor).await?;
+
+        for event in events {
+            if let Some(events) = grouped.get_mut(&event.aggregate) {
+                events.push(event);
+            }
+        }
+
+        Ok(grouped)
+    }
+
+    pub fn aggregate_prefix(&self) -> Option<&str> {
+        self.split_aggregate().map(|(prefix, _)| prefix)
+    }
+
+    pub fn aggregate_id(&self) -> Option<&str> {
+        self.split_aggregate().map(|(_, id)| id)
+    }
+
+    fn split_aggregate(&self) -> Option<(&str, &str)> {
+        aggregate::split(&self.aggregate).ok()
+    }
+
+    pub fn sort_key(&self) -> (u32, u16, &str) {
+        (self.timestamp, self.version, &self.id)
+    }
+
+    pub fn data_ref(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn metadata_ref(&self) -> Option<&[u8]> {
+        self.metadata.as_deref()
+    }
+
+    pub fn to_data<D: DeserializeOwned>(&self) -> Result<Option<D>, CodecError> {
         if self.name != std::any::type_name::<D>() {
             return Ok(None);
         }
 
-        ciborium::from_reader(&self.data[..])
+        self.decode_data().map(Some)
     }
 
-    pub fn to_metadata<M: serde::de::DeserializeOwned>(
+    pub fn to_data_versioned<D: DeserializeOwned>(
         &self,
-    ) -> Result<Option<M>, ciborium::de::Error<std::io::Error>> {
+        name: &str,
+        schema_version: u16,
+    ) -> Result<Option<D>, CodecError> {
+        if self.name != name || self.schema_version != schema_version {
+            return Ok(None);
+        }
+
+        self.decode_data().map(Some)
+    }
+
+    fn decode_data<D: DeserializeOwned>(&self) -> Result<D, CodecError> {
+        match self.content_type.as_str() {
+            Cbor::CONTENT_TYPE => Cbor::decode(&self.data),
+            Json::CONTENT_TYPE => Json::decode(&self.data),
+            content_type => Err(CodecError::UnknownContentType(content_type.to_owned())),
+        }
+    }
+
+    pub fn data_json(&self) -> Result<serde_json::Value, CodecError> {
+        match self.content_type.as_str() {
+            Cbor::CONTENT_TYPE => Cbor::decode(&self.data).map(cbor_to_json),
+            Json::CONTENT_TYPE => Json::decode(&self.data),
+            content_type => Err(CodecError::UnknownContentType(content_type.to_owned())),
+        }
+    }
+
+    pub fn to_data_with<C: Codec, D: DeserializeOwned>(&self) -> Result<Option<D>, CodecError> {
+        if self.name != std::any::type_name::<D>() {
+            return Ok(None);
+        }
+
+        self.check_content_type::<C>()?;
+
+        C::decode(&self.data).map(Some)
+    }
+
+    pub fn to_metadata<M: DeserializeOwned>(&self) -> Result<Option<M>, CodecError> {
+        if self.metadata.is_none() {
+            return Ok(None);
+        }
+
+        match self.content_type.as_str() {
+            Cbor::CONTENT_TYPE => self.to_metadata_with::<Cbor, M>(),
+            Json::CONTENT_TYPE => self.to_metadata_with::<Json, M>(),
+            content_type => Err(CodecError::UnknownContentType(content_type.to_owned())),
+        }
+    }
+
+    pub fn to_metadata_with<C: Codec, M: DeserializeOwned>(&self) -> Result<Option<M>, CodecError> {
+        self.check_content_type::<C>()?;
+
         match &self.metadata {
-            Some(metadata) => ciborium::from_reader(&metadata[..]),
+            Some(metadata) => C::decode(metadata).map(Some),
             _ => Ok(None),
         }
     }
+
+    fn check_content_type<C: Codec>(&self) -> Result<(), CodecError> {
+        if self.content_type != C::CONTENT_TYPE {
+            return Err(CodecError::ContentTypeMismatch {
+                expected: C::CONTENT_TYPE,
+                found: self.content_type.to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn to_metadata_lenient<M: DeserializeOwned>(&self) -> Option<M> {
+        match self.to_metadata() {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                tracing::warn!("event {} has malformed metadata: {e}", self.id);
+
+                None
+            }
+        }
+    }
+}
+
+fn cbor_to_json(value: ciborium::Value) -> serde_json::Value {
+    use ciborium::Value;
+
+    match value {
+        Value::Integer(i) => {
+            let i = i128::from(i);
+            match (i64::try_from(i), u64::try_from(i)) {
+                (Ok(i), _) => i.into(),
+                (_, Ok(i)) => i.into(),
+                _ => i.to_string().into(),
+            }
+        }
+        Value::Bytes(bytes) => bytes.into(),
+        Value::Float(f) => serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .unwrap_or_default(),
+        Value::Text(text) => text.into(),
+        Value::Bool(b) => b.into(),
+        Value::Tag(_, value) => cbor_to_json(*value),
+        Value::Array(values) => values.into_iter().map(cbor_to_json).collect(),
+        Value::Map(entries) => entries
+            .into_iter()
+            .map(|(k, v)| {
+                let k = match k {
+                    Value::Text(k) => k,
+                    k => cbor_to_json(k).to_string(),
+                };
+
+                (k, cbor_to_json(v))
+            })
+            .collect(),
+        _ => serde_json::Value::Null,
+    }
 }
 
 impl<'q, DB: Database> BindCursor<'q, DB> for Event
@@ -61,6 +328,43 @@ where
     }
 }
 
+impl<'r, R> FromRow<'r, R> for Event
+where
+    R: Row,
+    EventHeader: FromRow<'r, R>,
+    Vec<u8>: Decode<'r, R::Database> + Type<R::Database>,
+    &'r str: ColumnIndex<R>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        let header = EventHeader::from_row(row)?;
+        let data: Vec<u8> = row.try_get("data")?;
+
+        if data.is_empty() {
+            return Err(sqlx::Error::ColumnDecode {
+                index: "data".to_owned(),
+                source: format!(
+                    "payload of event {} is stored in event_data, read it through Event::reader or with_payload",
+                    header.id
+                )
+                .into(),
+            });
+        }
+
+        Ok(Self {
+            id: header.id,
+            name: header.name,
+            aggregate: header.aggregate,
+            stream: header.stream,
+            version: header.version,
+            schema_version: header.schema_version,
+            data,
+            metadata: row.try_get("metadata")?,
+            content_type: header.content_type,
+            timestamp: header.timestamp,
+        })
+    }
+}
+
 impl ToCursor for Event {
     type Cursor = EventCursor;
 
@@ -72,3 +376,702 @@ impl ToCursor for Event {
         }
     }
 }
+
+impl EventHeader {
+    pub fn reader<'args>() -> SqliteReader<'args, EventHeader> {
+        SqliteReader::new(format!("SELECT {HEADER_COLUMNS} FROM event"))
+    }
+}
+
+impl<'q, DB: Database> BindCursor<'q, DB> for EventHeader
+where
+    u16: Encode<'q, DB> + Type<DB>,
+    u32: Encode<'q, DB> + Type<DB>,
+    String: Encode<'q, DB> + Type<DB>,
+{
+    type Cursor = EventCursor;
+
+    fn bing_keys() -> Vec<&'static str> {
+        vec!["timestamp", "version", "id"]
+    }
+
+    fn bind_query<O>(
+        cursor: Self::Cursor,
+        query: QueryAs<'q, DB, O, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, O, <DB as Database>::Arguments<'q>> {
+        query.bind(cursor.t).bind(cursor.v).bind(cursor.i)
+    }
+}
+
+impl ToCursor for EventHeader {
+    type Cursor = EventCursor;
+
+    fn serialize_cursor(&self) -> EventCursor {
+        EventCursor {
+            i: self.id.clone(),
+            v: self.version,
+            t: self.timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Writer;
+    use rand::seq::SliceRandom;
+    use sqlx::{any::install_default_drivers, migrate::MigrateDatabase, Any};
+    use tracing_test::traced_test;
+
+    #[tokio::test]
+    async fn read_versions() {
+        let pool = get_pool("read_versions").await;
+
+        for version in 0..5 {
+            Writer::new("product/1")
+                .original_version(version)
+                .event(&Renamed {
+                    name: format!("Product {version}"),
+                })
+                .unwrap()
+                .write(&pool)
+                .await
+                .unwrap();
+        }
+
+        Writer::new("product/2")
+            .event(&Renamed {
+                name: "Product 2".to_owned(),
+            })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let events = Event::read_versions(&pool, "product/1", 1, 3)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events.iter().map(|e| e.version).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(
+            events[0].to_data::<Renamed>().unwrap().unwrap(),
+            Renamed {
+                name: "Product 1".to_owned()
+            }
+        );
+
+        let events = Event::read_versions(&pool, "product/1", 5, 10)
+            .await
+            .unwrap();
+
+        assert!(events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_stream_versions() {
+        let pool = get_pool("read_stream_versions").await;
+        write_streams(&pool).await;
+
+        let events = Event::read_versions(&pool, "product/1", 1, 3)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.stream.as_str(), e.version))
+                .collect::<Vec<_>>(),
+            vec![("", 2), ("", 3)]
+        );
+
+        let events = Event::read_stream_versions(&pool, "product/1", "billing", 1, 3)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.stream.as_str(), e.version))
+                .collect::<Vec<_>>(),
+            vec![("billing", 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn by_version() {
+        let pool = get_pool("by_version").await;
+
+        for version in 0..3 {
+            Writer::new("product/1")
+                .original_version(version)
+                .event(&Renamed {
+                    name: format!("Product {version}"),
+                })
+                .unwrap()
+                .write(&pool)
+                .await
+                .unwrap();
+        }
+
+        let event = Event::by_version(&pool, "product/1", 2)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(event.version, 2);
+        assert_eq!(
+            event.to_data::<Renamed>().unwrap().unwrap(),
+            Renamed {
+                name: "Product 1".to_owned()
+            }
+        );
+
+        let event = Event::by_version(&pool, "product/1", 4).await.unwrap();
+
+        assert_eq!(event, None);
+
+        let event = Event::by_version(&pool, "product/2", 1).await.unwrap();
+
+        assert_eq!(event, None);
+    }
+
+    #[tokio::test]
+    async fn by_stream_version() {
+        let pool = get_pool("by_stream_version").await;
+        write_streams(&pool).await;
+
+        let event = Event::by_version(&pool, "product/1", 1)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!((event.stream.as_str(), event.version), ("", 1));
+
+        let event = Event::by_stream_version(&pool, "product/1", "billing", 2)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!((event.stream.as_str(), event.version), ("billing", 2));
+        assert_eq!(
+            event.to_data::<Renamed>().unwrap().unwrap(),
+            Renamed {
+                name: "Product billing 1".to_owned()
+            }
+        );
+
+        let event = Event::by_stream_version(&pool, "product/1", "billing", 3)
+            .await
+            .unwrap();
+
+        assert_eq!(event, None);
+    }
+
+    #[tokio::test]
+    async fn latest_of_type() {
+        let pool = get_pool("latest_of_type").await;
+
+        for version in 0..6 {
+            let writer = Writer::new("product/1").original_version(version);
+            let writer = if version % 2 == 0 {
+                writer.event(&Renamed {
+                    name: format!("Product {version}"),
+                })
+            } else {
+                writer.event(&Archived {
+                    archived: version % 3 == 0,
+                })
+            };
+
+            writer.unwrap().write(&pool).await.unwrap();
+        }
+
+        let renamed = Event::latest_of_type::<Renamed>(&pool, "product/1")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            renamed,
+            Some(Renamed {
+                name: "Product 4".to_owned()
+            })
+        );
+
+        let archived = Event::latest_of_type::<Archived>(&pool, "product/1")
+            .await
+            .unwrap();
+
+        assert_eq!(archived, Some(Archived { archived: false }));
+
+        let renamed = Event::latest_of_type::<Renamed>(&pool, "product/2")
+            .await
+            .unwrap();
+
+        assert_eq!(renamed, None);
+    }
+
+    #[tokio::test]
+    async fn read_grouped() {
+        let pool = get_pool("read_grouped").await;
+
+        for id in 0..4 {
+            for version in 0..id + 2 {
+                Writer::new(format!("product/{id}"))
+                    .original_version(version)
+                    .event(&Renamed {
+                        name: format!("Product {id}.{version}"),
+                    })
+                    .unwrap()
+                    .write(&pool)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let grouped = Event::read_grouped(&pool, ["product/1", "product/3", "product/9"])
+            .await
+            .unwrap();
+
+        assert_eq!(grouped.len(), 3);
+        assert_eq!(
+            grouped["product/1"]
+                .iter()
+                .map(|e| e.version)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            grouped["product/3"]
+                .iter()
+                .map(|e| e.version)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert!(grouped["product/3"]
+            .iter()
+            .all(|e| e.aggregate == "product/3"));
+        assert!(grouped["product/9"].is_empty());
+
+        let grouped = Event::read_grouped(&pool, Vec::<String>::new())
+            .await
+            .unwrap();
+
+        assert!(grouped.is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_grouped_streams() {
+        let pool = get_pool("read_grouped_streams").await;
+        write_streams(&pool).await;
+
+        let grouped = Event::read_grouped(&pool, ["product/1"]).await.unwrap();
+
+        assert_eq!(
+            grouped["product/1"]
+                .iter()
+                .map(|e| (e.stream.as_str(), e.version))
+                .collect::<Vec<_>>(),
+            vec![("", 1), ("", 2), ("", 3), ("billing", 1), ("billing", 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn sort_key() {
+        let pool = get_pool("sort_key").await;
+
+        for id in 0..3 {
+            for version in 0..4 {
+                Writer::new(format!("product/{id}"))
+                    .original_version(version)
+                    .event(&Renamed {
+                        name: format!("Product {id}.{version}"),
+                    })
+                    .unwrap()
+                    .write(&pool)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        sqlx::query("UPDATE event SET timestamp = abs(random()) % 3")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let expected =
+            sqlx::query_as::<_, Event>("SELECT * FROM event ORDER BY timestamp, version, id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+
+        let mut events = expected.clone();
+        events.shuffle(&mut rand::rng());
+        events.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+
+        assert_eq!(
+            events.iter().map(|e| &e.id).collect::<Vec<_>>(),
+            expected.iter().map(|e| &e.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn aggregate_id_and_prefix() {
+        let mut event = Event {
+            id: "01JDZ7C5S0V3BE2E8X7DQPBHNT".to_owned(),
+            name: std::any::type_name::<Renamed>().to_owned(),
+            aggregate: "product/1".to_owned(),
+            stream: Default::default(),
+            version: 1,
+            schema_version: 1,
+            data: vec![],
+            metadata: None,
+            content_type: Cbor::CONTENT_TYPE.to_owned(),
+            timestamp: 0,
+        };
+
+        assert_eq!(event.aggregate_prefix(), Some("product"));
+        assert_eq!(event.aggregate_id(), Some("1"));
+
+        event.aggregate = "01JDZ7C5S0V3BE2E8X7DQPBHNT".to_owned();
+
+        assert_eq!(event.aggregate_prefix(), None);
+        assert_eq!(event.aggregate_id(), None);
+
+        event.aggregate = "file/docs/2024/report.pdf".to_owned();
+
+        assert_eq!(event.aggregate_prefix(), Some("file"));
+        assert_eq!(event.aggregate_id(), Some("docs/2024/report.pdf"));
+    }
+
+    #[test]
+    fn data_and_metadata_ref() {
+        let mut event = Event {
+            id: "01JDZ7C5S0V3BE2E8X7DQPBHNT".to_owned(),
+            name: std::any::type_name::<Renamed>().to_owned(),
+            aggregate: "product/1".to_owned(),
+            stream: Default::default(),
+            version: 1,
+            schema_version: 1,
+            data: vec![1, 2, 3],
+            metadata: Some(vec![4, 5]),
+            content_type: Cbor::CONTENT_TYPE.to_owned(),
+            timestamp: 0,
+        };
+
+        assert_eq!(event.data_ref(), &[1, 2, 3]);
+        assert_eq!(event.data_ref().as_ptr(), event.data.as_ptr());
+        assert_eq!(event.metadata_ref(), Some(&[4, 5][..]));
+        assert_eq!(
+            event.metadata_ref().map(|m| m.as_ptr()),
+            event.metadata.as_ref().map(|m| m.as_ptr())
+        );
+
+        event.metadata = None;
+
+        assert_eq!(event.metadata_ref(), None);
+    }
+
+    #[test]
+    #[traced_test]
+    fn to_metadata_lenient() {
+        let mut metadata = vec![];
+        ciborium::into_writer(
+            &Renamed {
+                name: "Bob".to_owned(),
+            },
+            &mut metadata,
+        )
+        .unwrap();
+
+        let mut event = Event {
+            id: "01JDZ7C5S0V3BE2E8X7DQPBHNT".to_owned(),
+            name: std::any::type_name::<Renamed>().to_owned(),
+            aggregate: "product/1".to_owned(),
+            stream: Default::default(),
+            version: 1,
+            schema_version: 1,
+            data: vec![],
+            metadata: Some(metadata),
+            content_type: Cbor::CONTENT_TYPE.to_owned(),
+            timestamp: 0,
+        };
+
+        assert_eq!(
+            event.to_metadata_lenient::<Renamed>(),
+            Some(Renamed {
+                name: "Bob".to_owned()
+            })
+        );
+        assert!(!logs_contain("malformed metadata"));
+
+        event.metadata = Some(vec![0xff, 0x00, 0x13]);
+
+        assert!(event.to_metadata::<Renamed>().is_err());
+        assert_eq!(event.to_metadata_lenient::<Renamed>(), None);
+        assert!(logs_contain(
+            "event 01JDZ7C5S0V3BE2E8X7DQPBHNT has malformed metadata"
+        ));
+    }
+
+    #[tokio::test]
+    async fn split_payload() {
+        let pool = get_pool("split_payload").await;
+
+        Writer::new("product/1")
+            .event(&Renamed {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        Writer::new("product/1")
+            .original_version(1)
+            .split_payload()
+            .event_with_metadata(
+                &Renamed {
+                    name: "Product 2".to_owned(),
+                },
+                &Renamed {
+                    name: "Bob".to_owned(),
+                },
+            )
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let stored = sqlx::query_as::<_, (u16, Vec<u8>, Option<Vec<u8>>)>(
+            "SELECT version, data, metadata FROM event ORDER BY version",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+        assert!(!stored[0].1.is_empty());
+        assert_eq!((stored[1].1.len(), stored[1].2.is_none()), (0, true));
+
+        let err = SqliteReader::<Event>::new("SELECT * FROM event")
+            .forward(10, None)
+            .read(&pool)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("is stored in event_data"));
+
+        let mut reader = EventHeader::reader().forward(10, None);
+
+        assert_eq!(
+            reader.explain().0,
+            "SELECT id, name, aggregate, stream, version, schema_version, content_type, timestamp FROM event ORDER BY timestamp ASC, version ASC, id ASC LIMIT 11"
+        );
+
+        let headers = reader
+            .read(&pool)
+            .await
+            .unwrap()
+            .edges
+            .into_iter()
+            .map(|e| e.node)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            headers.iter().map(|h| h.version).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let mut reader = EventHeader::reader()
+            .raw_where("aggregate = ?")
+            .unwrap()
+            .bind("product/1")
+            .unwrap()
+            .with_payload()
+            .forward(1, None);
+
+        assert!(reader
+            .explain()
+            .0
+            .contains("COALESCE(event_data.data, event.data) AS data"));
+
+        let first = reader.read(&pool).await.unwrap();
+
+        assert!(first.page_info.has_next_page);
+        assert_eq!(
+            first.edges[0].node.to_data::<Renamed>().unwrap().unwrap(),
+            Renamed {
+                name: "Product 1".to_owned()
+            }
+        );
+
+        let second = EventHeader::reader()
+            .raw_where("aggregate = ?")
+            .unwrap()
+            .bind("product/1")
+            .unwrap()
+            .with_payload()
+            .forward(1, Some(first.page_info.end_cursor.unwrap()))
+            .read(&pool)
+            .await
+            .unwrap();
+
+        let event = second.edges[0].node.clone();
+
+        assert_eq!(event.id, headers[1].id);
+        assert_eq!(
+            event.to_data::<Renamed>().unwrap().unwrap(),
+            Renamed {
+                name: "Product 2".to_owned()
+            }
+        );
+        assert_eq!(
+            event.to_metadata::<Renamed>().unwrap().unwrap(),
+            Renamed {
+                name: "Bob".to_owned()
+            }
+        );
+
+        let events = Event::reader()
+            .forward(10, None)
+            .read(&pool)
+            .await
+            .unwrap()
+            .edges
+            .into_iter()
+            .map(|e| e.node.to_data::<Renamed>().unwrap().unwrap().name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(events, vec!["Product 1", "Product 2"]);
+
+        let events = Event::read_versions(&pool, "product/1", 0, 2)
+            .await
+            .unwrap();
+
+        assert_eq!(events[1], event);
+    }
+
+    async fn write_streams(pool: &SqlitePool) {
+        for (stream, count) in [("billing", 2), ("", 3)] {
+            for version in 0..count {
+                Writer::new("product/1")
+                    .stream(stream)
+                    .original_version(version)
+                    .event(&Renamed {
+                        name: format!("Product {stream} {version}"),
+                    })
+                    .unwrap()
+                    .write(pool)
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn to_metadata_custom_codec() {
+        let mut event = Event {
+            id: "01JDZ7C5S0V3BE2E8X7DQPBHNT".to_owned(),
+            name: std::any::type_name::<Renamed>().to_owned(),
+            aggregate: "product/1".to_owned(),
+            stream: Default::default(),
+            version: 1,
+            schema_version: 1,
+            data: vec![],
+            metadata: None,
+            content_type: "application/msgpack".to_owned(),
+            timestamp: 0,
+        };
+
+        assert_eq!(event.to_metadata::<Renamed>().unwrap(), None);
+        assert_eq!(event.to_metadata_lenient::<Renamed>(), None);
+        assert!(!logs_contain("malformed metadata"));
+
+        event.metadata = Some(vec![0x81]);
+
+        assert!(matches!(
+            event.to_metadata::<Renamed>(),
+            Err(CodecError::UnknownContentType(_))
+        ));
+    }
+
+    #[test]
+    fn data_json_cbor() {
+        let mut data = vec![];
+        ciborium::into_writer(
+            &ciborium::Value::Map(vec![
+                (
+                    ciborium::Value::Text("name".to_owned()),
+                    ciborium::Value::Text("Product 1".to_owned()),
+                ),
+                (
+                    ciborium::Value::Integer(7.into()),
+                    ciborium::Value::Bytes(vec![1, 2, 3]),
+                ),
+                (
+                    ciborium::Value::Text("price".to_owned()),
+                    ciborium::Value::Float(9.5),
+                ),
+                (
+                    ciborium::Value::Text("tagged".to_owned()),
+                    ciborium::Value::Tag(
+                        1,
+                        Box::new(ciborium::Value::Integer(1_700_000_000.into())),
+                    ),
+                ),
+            ]),
+            &mut data,
+        )
+        .unwrap();
+
+        let event = Event {
+            id: "01JDZ7C5S0V3BE2E8X7DQPBHNT".to_owned(),
+            name: std::any::type_name::<Renamed>().to_owned(),
+            aggregate: "product/1".to_owned(),
+            stream: Default::default(),
+            version: 1,
+            schema_version: 1,
+            data,
+            metadata: None,
+            content_type: Cbor::CONTENT_TYPE.to_owned(),
+            timestamp: 0,
+        };
+
+        assert_eq!(
+            event.data_json().unwrap(),
+            serde_json::json!({
+                "name": "Product 1",
+                "7": [1, 2, 3],
+                "price": 9.5,
+                "tagged": 1_700_000_000,
+            })
+        );
+    }
+
+    async fn get_pool(key: impl Into<String>) -> SqlitePool {
+        let key = key.into();
+        let dsn = format!("sqlite:../target/event_{key}.db");
+
+        install_default_drivers();
+        let _ = Any::drop_database(&dsn).await;
+        Any::create_database(&dsn).await.unwrap();
+
+        let pool = SqlitePool::connect(&dsn).await.unwrap();
+        sqlx::migrate!("../migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Renamed {
+        pub name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Archived {
+        pub archived: bool,
+    }
+}
@@ -0,0 +1,97 @@
+use sqlx::SqlitePool;
+
+#[derive(thiserror::Error, Debug)]
+pub enum HealthError {
+    #[error("missing event table")]
+    MissingEventTable,
+
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Health {
+    pub migration_version: Option<i64>,
+}
+
+pub async fn health(executor: &SqlitePool) -> Result<Health, HealthError> {
+    sqlx::query("SELECT 1").execute(executor).await?;
+
+    if !table_exists(executor, "event").await? {
+        return Err(HealthError::MissingEventTable);
+    }
+
+    let migration_version = if table_exists(executor, "_sqlx_migrations").await? {
+        sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1",
+        )
+        .fetch_one(executor)
+        .await?
+    } else {
+        None
+    };
+
+    Ok(Health { migration_version })
+}
+
+async fn table_exists(executor: &SqlitePool, name: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = $1)",
+    )
+    .bind(name)
+    .fetch_one(executor)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{any::install_default_drivers, migrate::MigrateDatabase, Any};
+
+    #[tokio::test]
+    async fn migrated() {
+        let pool = get_pool("migrated").await;
+        let migrator = sqlx::migrate!("../migrations");
+        migrator.run(&pool).await.unwrap();
+
+        assert_eq!(
+            health(&pool).await.unwrap(),
+            Health {
+                migration_version: migrator.iter().map(|m| m.version).max(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_schema() {
+        let pool = get_pool("missing_schema").await;
+
+        assert!(matches!(
+            health(&pool).await,
+            Err(HealthError::MissingEventTable)
+        ));
+
+        sqlx::query("CREATE TABLE event (id TEXT PRIMARY KEY)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            health(&pool).await.unwrap(),
+            Health {
+                migration_version: None
+            }
+        );
+    }
+
+    async fn get_pool(key: impl Into<String>) -> SqlitePool {
+        let key = key.into();
+        let dsn = format!("sqlite:../target/health_{key}.db");
+
+        install_default_drivers();
+        let _ = Any::drop_database(&dsn).await;
+        Any::create_database(&dsn).await.unwrap();
+
+        SqlitePool::connect(&dsn).await.unwrap()
+    }
+}
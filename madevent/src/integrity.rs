@@ -0,0 +1,158 @@
+use sqlx::SqlitePool;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Integrity {
+    pub gaps: Vec<u16>,
+    pub duplicate_versions: Vec<u16>,
+    pub out_of_order_timestamps: Vec<u16>,
+}
+
+impl Integrity {
+    pub fn is_ok(&self) -> bool {
+        self.gaps.is_empty()
+            && self.duplicate_versions.is_empty()
+            && self.out_of_order_timestamps.is_empty()
+    }
+}
+
+pub async fn verify_aggregate(
+    executor: &SqlitePool,
+    aggregate: impl Into<String>,
+) -> Result<Integrity, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, u16, u32)>(
+        "SELECT stream, version, timestamp FROM event WHERE aggregate = $1 ORDER BY stream, version, id",
+    )
+    .bind(aggregate.into())
+    .fetch_all(executor)
+    .await?;
+
+    let mut integrity = Integrity::default();
+    let mut current_stream = None;
+    let mut expected = 1;
+    let mut last_timestamp = 0;
+
+    for (stream, version, timestamp) in rows {
+        if current_stream.as_ref() != Some(&stream) {
+            current_stream = Some(stream);
+            expected = 1;
+            last_timestamp = 0;
+        }
+
+        if version < expected {
+            integrity.duplicate_versions.push(version);
+        } else {
+            integrity.gaps.extend(expected..version);
+            expected = version + 1;
+        }
+
+        if timestamp < last_timestamp {
+            integrity.out_of_order_timestamps.push(version);
+        }
+
+        last_timestamp = last_timestamp.max(timestamp);
+    }
+
+    Ok(integrity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Writer;
+    use serde::{Deserialize, Serialize};
+    use sqlx::{any::install_default_drivers, migrate::MigrateDatabase, Any};
+
+    #[tokio::test]
+    async fn clean_aggregate() {
+        let pool = get_pool("clean_aggregate").await;
+        write_events(&pool, 5).await;
+
+        let integrity = verify_aggregate(&pool, "product/1").await.unwrap();
+
+        assert!(integrity.is_ok());
+    }
+
+    #[tokio::test]
+    async fn gap_and_timestamps() {
+        let pool = get_pool("gap_and_timestamps").await;
+        write_events(&pool, 5).await;
+
+        sqlx::query("DELETE FROM event WHERE aggregate = 'product/1' AND version IN (2, 3)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE event SET timestamp = timestamp + 100 WHERE version = 4")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let integrity = verify_aggregate(&pool, "product/1").await.unwrap();
+
+        assert!(!integrity.is_ok());
+        assert_eq!(
+            integrity,
+            Integrity {
+                gaps: vec![2, 3],
+                duplicate_versions: vec![],
+                out_of_order_timestamps: vec![5],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn streams() {
+        let pool = get_pool("streams").await;
+
+        for stream in ["billing", "shipping"] {
+            for version in 0..3 {
+                Writer::new("product/1")
+                    .stream(stream)
+                    .original_version(version)
+                    .event(&Renamed {
+                        name: format!("Product {version}"),
+                    })
+                    .unwrap()
+                    .write(&pool)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let integrity = verify_aggregate(&pool, "product/1").await.unwrap();
+
+        assert!(integrity.is_ok());
+    }
+
+    async fn write_events(pool: &SqlitePool, count: u16) {
+        for version in 0..count {
+            Writer::new("product/1")
+                .original_version(version)
+                .event(&Renamed {
+                    name: format!("Product {version}"),
+                })
+                .unwrap()
+                .write(pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn get_pool(key: impl Into<String>) -> SqlitePool {
+        let key = key.into();
+        let dsn = format!("sqlite:../target/integrity_{key}.db");
+
+        install_default_drivers();
+        let _ = Any::drop_database(&dsn).await;
+        Any::create_database(&dsn).await.unwrap();
+
+        let pool = SqlitePool::connect(&dsn).await.unwrap();
+        sqlx::migrate!("../migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Renamed {
+        pub name: String,
+    }
+}
@@ -1,16 +1,27 @@
+mod aggregate;
+mod codec;
 mod cursor;
 mod event;
+mod health;
+mod integrity;
 mod reader;
+pub mod schema;
 mod writer;
 
 use futures::{stream, Stream};
 use ulid::Ulid;
 
-pub use cursor::{BindCursor, Cursor, ToCursor};
-pub use event::Event;
+pub use aggregate::{Aggregate, AggregateError};
+pub use codec::{Cbor, Codec, CodecError, Json};
+pub use cursor::{BindCursor, Cursor, Error as CursorError, ToCursor};
+pub use event::{Event, EventCursor, EventError, EventHeader};
+pub use health::{health, Health, HealthError};
+pub use integrity::{verify_aggregate, Integrity};
 pub type SqliteReader<'args, O> = Reader<'args, sqlx::Sqlite, O>;
-pub use reader::Reader;
-pub use writer::Writer;
+pub use reader::{
+    Args, Edge, Error as ReaderError, JsonSelect, Order, PageInfo, ReadResult, Reader,
+};
+pub use writer::{Writer, WriterError};
 
 #[allow(dead_code)]
 pub struct MadEvent {
@@ -1,6 +1,11 @@
-use crate::{BindCursor, Cursor, ToCursor};
+use crate::event::{EVENT_SOURCE, HEADER_COLUMNS};
+use crate::{BindCursor, Codec, Cursor, Event, EventHeader, Json, ToCursor};
 use serde::{Deserialize, Serialize};
-use sqlx::{Arguments, Database, Encode, Executor, FromRow, IntoArguments, QueryBuilder, Type};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{
+    Arguments, ColumnIndex, Database, Decode, Encode, Executor, FromRow, IntoArguments,
+    QueryBuilder, Row, Sqlite, Type,
+};
 use std::marker::PhantomData;
 
 #[derive(thiserror::Error, Debug)]
@@ -13,6 +18,18 @@ pub enum Error {
 
     #[error("cbor de")]
     CiboriumSer(#[from] ciborium::ser::Error<std::io::Error>),
+
+    #[error("invalid identifier: {0}")]
+    InvalidIdentifier(String),
+
+    #[error("invalid placeholder in: {0}")]
+    InvalidPlaceholder(String),
+
+    #[error("bind count mismatch: expected {expected}, found {found}")]
+    BindCountMismatch { expected: usize, found: usize },
+
+    #[error("ambiguous args: first/after and last/before can't be combined")]
+    AmbiguousArgs,
 }
 
 pub struct Reader<'args, DB, O>
@@ -27,6 +44,10 @@ where
     qb_args: DB::Arguments<'args>,
     phantom_o: PhantomData<O>,
     order: Order,
+    order_by: Vec<(String, Order)>,
+    has_where: bool,
+    expected_binds: Option<usize>,
+    compact_cursors: bool,
     args: Args,
 }
 
@@ -39,11 +60,18 @@ where
     O: 'args + BindCursor<'args, DB> + ToCursor,
 {
     pub fn new(sql: impl Into<String>) -> Self {
+        let sql = sql.into();
+        let has_where = sql.contains(" WHERE ");
+
         Self {
             qb: QueryBuilder::new(sql),
             qb_args: DB::Arguments::default(),
             phantom_o: PhantomData,
             order: Order::Asc,
+            order_by: vec![],
+            has_where,
+            expected_binds: None,
+            compact_cursors: false,
             args: Default::default(),
         }
     }
@@ -56,18 +84,79 @@ where
         Ok(self)
     }
 
+    pub fn raw_where(mut self, clause: impl Into<String>) -> Result<Self, Error> {
+        let pos = self.expected_binds.unwrap_or(self.qb_args.len());
+        let (clause, count) = number_placeholders(&clause.into(), pos)?;
+
+        let keyword = if self.has_where { "AND" } else { "WHERE" };
+        self.qb.push(format!(" {keyword} ({clause})"));
+        self.has_where = true;
+        self.expected_binds = Some(pos + count);
+
+        Ok(self)
+    }
+
+    pub fn latest_per(mut self, column: impl Into<String>) -> Result<Self, Error> {
+        let column = column.into();
+        if !is_identifier(&column) {
+            return Err(Error::InvalidIdentifier(column));
+        }
+
+        let keys = O::bing_keys()
+            .iter()
+            .map(|k| format!("{k} DESC"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        self.qb = QueryBuilder::new(format!(
+            "SELECT * FROM (SELECT *, ROW_NUMBER() OVER (PARTITION BY {column} ORDER BY {keys}) AS latest_rank FROM ({}) AS base) AS latest WHERE latest_rank = 1",
+            self.qb.sql()
+        ));
+        self.has_where = true;
+
+        Ok(self)
+    }
+
     pub fn order(mut self, value: Order) -> Self {
         self.order = value;
 
         self
     }
 
+    pub fn then_order_by(mut self, key: impl Into<String>, order: Order) -> Result<Self, Error> {
+        let key = key.into();
+        if !is_identifier(&key) {
+            return Err(Error::InvalidIdentifier(key));
+        }
+
+        self.order_by.push((key, order));
+
+        Ok(self)
+    }
+
+    pub fn compact_cursors(mut self) -> Self {
+        self.compact_cursors = true;
+
+        self
+    }
+
     pub fn args(mut self, value: Args) -> Self {
         self.args = value;
 
         self
     }
 
+    pub fn page(self, args: Args) -> Result<Self, Error> {
+        let is_forward = args.first.is_some() || args.after.is_some();
+        let is_backward = args.last.is_some() || args.before.is_some();
+
+        if is_forward && is_backward {
+            return Err(Error::AmbiguousArgs);
+        }
+
+        Ok(self.args(args))
+    }
+
     pub fn desc(self) -> Self {
         self.order(Order::Desc)
     }
@@ -92,13 +181,174 @@ where
     where
         E: 'a + Executor<'a, Database = DB>,
     {
+        self.check_binds()?;
         let (limit, cursor) = self.build();
 
         let mut query = sqlx::query_as_with::<_, O, _>(self.qb.sql(), self.qb_args.clone());
         if let Some(cursor) = cursor {
             query = O::bind_cursor(&cursor, query)?;
         }
-        let mut rows = query.fetch_all(executor).await?;
+        let rows = query.fetch_all(executor).await?;
+
+        self.to_result(rows, limit)
+    }
+
+    pub async fn read_with_count<'a, E>(
+        &'args mut self,
+        executor: E,
+    ) -> Result<(ReadResult<O>, i64), Error>
+    where
+        E: 'a + Executor<'a, Database = DB>,
+        i64: for<'r> Decode<'r, DB> + Type<DB>,
+        for<'r> &'r str: ColumnIndex<DB::Row>,
+    {
+        let value_sql = format!(
+            "SELECT COUNT(*) AS page_value FROM ({}) AS base",
+            self.qb.sql()
+        );
+
+        self.read_with_value(executor, value_sql).await
+    }
+
+    pub async fn read_with_neighbors<'a, E>(
+        &'args mut self,
+        executor: E,
+    ) -> Result<ReadResult<O>, Error>
+    where
+        E: 'a + Executor<'a, Database = DB>,
+        i64: for<'r> Decode<'r, DB> + Type<DB>,
+        for<'r> &'r str: ColumnIndex<DB::Row>,
+    {
+        let is_backward = self.is_backward();
+        let has_cursor = if is_backward {
+            self.args.before.is_some()
+        } else {
+            self.args.after.is_some()
+        };
+
+        let value_sql = if has_cursor {
+            format!(
+                "SELECT EXISTS(SELECT 1 FROM ({}) AS base WHERE NOT ({})) AS page_value",
+                self.qb.sql(),
+                self.build_cursor_expr(O::bing_keys(), self.qb_args.len() + 1)
+            )
+        } else {
+            "SELECT 0 AS page_value".to_owned()
+        };
+
+        let (mut result, has_opposite) = self.read_with_value(executor, value_sql).await?;
+        result.page_info.start_cursor = result.edges.first().map(|e| e.cursor.clone());
+        result.page_info.end_cursor = result.edges.last().map(|e| e.cursor.clone());
+
+        if is_backward {
+            result.page_info.has_next_page = has_opposite != 0;
+        } else {
+            result.page_info.has_previous_page = has_opposite != 0;
+        }
+
+        Ok(result)
+    }
+
+    async fn read_with_value<'a, E>(
+        &'args mut self,
+        executor: E,
+        value_sql: String,
+    ) -> Result<(ReadResult<O>, i64), Error>
+    where
+        E: 'a + Executor<'a, Database = DB>,
+        i64: for<'r> Decode<'r, DB> + Type<DB>,
+        for<'r> &'r str: ColumnIndex<DB::Row>,
+    {
+        self.check_binds()?;
+        let (limit, cursor) = self.build();
+
+        self.qb = QueryBuilder::new(format!(
+            "SELECT * FROM ({value_sql}) AS value LEFT JOIN (SELECT 1 AS page_found, * FROM ({}) AS base) AS page ON 1 = 1 ORDER BY {}",
+            self.qb.sql(),
+            self.build_order_expr()
+        ));
+
+        let mut query =
+            sqlx::query_as_with::<_, PageRow<O>, _>(self.qb.sql(), self.qb_args.clone());
+        if let Some(cursor) = cursor {
+            query = O::bind_cursor(&cursor, query)?;
+        }
+        let rows = query.fetch_all(executor).await?;
+        let value = rows.first().map(|r| r.value).unwrap_or_default();
+        let rows = rows.into_iter().filter_map(|r| r.node).collect();
+
+        Ok((self.to_result(rows, limit)?, value))
+    }
+
+    pub async fn count_distinct<'a, E>(
+        &'args mut self,
+        column: impl Into<String>,
+        executor: E,
+    ) -> Result<i64, Error>
+    where
+        E: 'a + Executor<'a, Database = DB>,
+        i64: for<'r> Decode<'r, DB> + Type<DB>,
+        usize: ColumnIndex<DB::Row>,
+    {
+        let column = column.into();
+        if !is_identifier(&column) {
+            return Err(Error::InvalidIdentifier(column));
+        }
+
+        self.check_binds()?;
+        self.qb = QueryBuilder::new(format!(
+            "SELECT COUNT(DISTINCT {column}) FROM ({}) AS base",
+            self.qb.sql()
+        ));
+
+        Ok(
+            sqlx::query_scalar_with::<_, i64, _>(self.qb.sql(), self.qb_args.clone())
+                .fetch_one(executor)
+                .await?,
+        )
+    }
+
+    pub async fn before_one<'a, E>(
+        &'args mut self,
+        cursor: Cursor,
+        executor: E,
+    ) -> Result<Option<O>, Error>
+    where
+        E: 'a + Executor<'a, Database = DB>,
+    {
+        self.args = Args {
+            last: Some(1),
+            before: Some(cursor),
+            ..Default::default()
+        };
+
+        let result = self.read(executor).await?;
+
+        Ok(result.edges.into_iter().next().map(|e| e.node))
+    }
+
+    pub fn explain(&mut self) -> (String, usize) {
+        let sql = self.qb.sql().to_owned();
+        let (_, cursor) = self.build();
+        let explained = self.qb.sql().to_owned();
+        let binds = self.qb_args.len() + cursor.map(|_| O::bing_keys().len()).unwrap_or_default();
+
+        self.qb = QueryBuilder::new(sql);
+
+        (explained, binds)
+    }
+
+    fn check_binds(&self) -> Result<(), Error> {
+        match self.expected_binds {
+            Some(expected) if expected != self.qb_args.len() => Err(Error::BindCountMismatch {
+                expected,
+                found: self.qb_args.len(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn to_result(&self, mut rows: Vec<O>, limit: u16) -> Result<ReadResult<O>, Error> {
         let has_more = rows.len() > limit as usize;
 
         if has_more {
@@ -107,10 +357,13 @@ where
 
         let mut edges = vec![];
         for node in rows.into_iter() {
-            edges.push(Edge {
-                cursor: node.to_cursor()?,
-                node,
-            });
+            let cursor = if self.compact_cursors {
+                node.to_compact_cursor()?
+            } else {
+                node.to_cursor()?
+            };
+
+            edges.push(Edge { cursor, node });
         }
 
         if self.is_backward() {
@@ -148,7 +401,7 @@ where
 
         if cursor.is_some() {
             let cursor_expr = self.build_cursor_expr(O::bing_keys(), self.qb_args.len() + 1);
-            let where_expr = if self.qb.sql().contains(" WHERE ") {
+            let where_expr = if self.has_where {
                 format!("AND ({cursor_expr})")
             } else {
                 format!("WHERE {cursor_expr}")
@@ -157,21 +410,35 @@ where
             self.qb.push(format!(" {where_expr}"));
         }
 
+        let order_expr = self.build_order_expr();
+
+        self.qb
+            .push(format!(" ORDER BY {order_expr} LIMIT {}", limit + 1));
+
+        (limit, cursor)
+    }
+
+    fn build_order_expr(&self) -> String {
         let order = match (&self.order, self.is_backward()) {
             (Order::Asc, true) | (Order::Desc, false) => "DESC",
             (Order::Asc, false) | (Order::Desc, true) => "ASC",
         };
 
-        let order_expr = O::bing_keys()
+        let mut order_expr = O::bing_keys()
             .iter()
             .map(|k| format!("{k} {order}"))
-            .collect::<Vec<_>>()
-            .join(", ");
+            .collect::<Vec<_>>();
 
-        self.qb
-            .push(format!(" ORDER BY {order_expr} LIMIT {}", limit + 1));
+        for (key, key_order) in self.order_by.iter() {
+            let key_order = match (key_order, self.is_backward()) {
+                (Order::Asc, true) | (Order::Desc, false) => "DESC",
+                (Order::Asc, false) | (Order::Desc, true) => "ASC",
+            };
 
-        (limit, cursor)
+            order_expr.push(format!("{key} {key_order}"));
+        }
+
+        order_expr.join(", ")
     }
 
     fn build_cursor_expr(&self, mut keys: Vec<&str>, pos: usize) -> String {
@@ -200,6 +467,188 @@ where
     }
 }
 
+impl<'args, O> Reader<'args, Sqlite, O>
+where
+    O: for<'r> FromRow<'r, SqliteRow>,
+    O: 'args + Send + Unpin,
+    O: 'args + BindCursor<'args, Sqlite> + ToCursor,
+{
+    pub fn select_json(
+        mut self,
+        path: impl Into<String>,
+    ) -> Result<Reader<'args, Sqlite, JsonSelect<O>>, sqlx::error::BoxDynError>
+    where
+        JsonSelect<O>: for<'r> FromRow<'r, SqliteRow> + BindCursor<'args, Sqlite>,
+    {
+        self.check_binds()?;
+        self.qb_args.add(path.into())?;
+        let expected_binds = Some(self.qb_args.len());
+
+        let qb = QueryBuilder::new(format!(
+            "SELECT *, json_quote(json_extract(CAST(data AS TEXT), ${})) AS json_value FROM ({}) AS base WHERE content_type = '{}'",
+            self.qb_args.len(),
+            self.qb.sql(),
+            Json::CONTENT_TYPE
+        ));
+
+        Ok(Reader {
+            qb,
+            qb_args: self.qb_args,
+            phantom_o: PhantomData,
+            order: self.order,
+            order_by: self.order_by,
+            has_where: true,
+            expected_binds,
+            compact_cursors: self.compact_cursors,
+            args: self.args,
+        })
+    }
+}
+
+impl<'args> Reader<'args, Sqlite, EventHeader> {
+    pub fn with_payload(self) -> Reader<'args, Sqlite, Event> {
+        let header_select = format!("SELECT {HEADER_COLUMNS} FROM event");
+        let sql = match self.qb.sql().strip_prefix(&header_select) {
+            Some(rest) => format!("SELECT * FROM {EVENT_SOURCE}{rest}"),
+            _ => format!(
+                "SELECT * FROM {EVENT_SOURCE} WHERE id IN (SELECT id FROM ({}) AS header)",
+                self.qb.sql()
+            ),
+        };
+
+        Reader {
+            has_where: sql.contains(" WHERE "),
+            qb: QueryBuilder::new(sql),
+            qb_args: self.qb_args,
+            phantom_o: PhantomData,
+            order: self.order,
+            order_by: self.order_by,
+            expected_binds: self.expected_binds,
+            compact_cursors: self.compact_cursors,
+            args: self.args,
+        }
+    }
+}
+
+struct PageRow<O> {
+    node: Option<O>,
+    value: i64,
+}
+
+impl<'r, R, O> FromRow<'r, R> for PageRow<O>
+where
+    R: Row,
+    O: FromRow<'r, R>,
+    i64: Decode<'r, R::Database> + Type<R::Database>,
+    &'r str: ColumnIndex<R>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        let page_found: Option<i64> = row.try_get("page_found")?;
+        let node = match page_found {
+            Some(_) => Some(O::from_row(row)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            node,
+            value: row.try_get("page_value")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSelect<O> {
+    pub node: O,
+    pub value: serde_json::Value,
+}
+
+impl<'r, R, O> FromRow<'r, R> for JsonSelect<O>
+where
+    R: Row,
+    O: FromRow<'r, R>,
+    String: Decode<'r, R::Database> + Type<R::Database>,
+    &'r str: ColumnIndex<R>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        let value: String = row.try_get("json_value")?;
+        let value = serde_json::from_str(&value).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "json_value".to_owned(),
+            source: Box::new(e),
+        })?;
+
+        Ok(Self {
+            node: O::from_row(row)?,
+            value,
+        })
+    }
+}
+
+impl<'q, DB: Database, O: BindCursor<'q, DB>> BindCursor<'q, DB> for JsonSelect<O> {
+    type Cursor = O::Cursor;
+
+    fn bing_keys() -> Vec<&'static str> {
+        O::bing_keys()
+    }
+
+    fn bind_query<O2>(
+        cursor: Self::Cursor,
+        query: sqlx::query::QueryAs<'q, DB, O2, DB::Arguments<'q>>,
+    ) -> sqlx::query::QueryAs<'q, DB, O2, DB::Arguments<'q>> {
+        O::bind_query(cursor, query)
+    }
+}
+
+impl<O: ToCursor> ToCursor for JsonSelect<O> {
+    type Cursor = O::Cursor;
+
+    fn serialize_cursor(&self) -> Self::Cursor {
+        self.node.serialize_cursor()
+    }
+}
+
+fn number_placeholders(clause: &str, mut pos: usize) -> Result<(String, usize), Error> {
+    let mut sql = String::with_capacity(clause.len());
+    let mut quote = None;
+    let mut count = 0;
+    let mut chars = clause.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '?' | '$') if chars.peek().is_some_and(|c| c.is_ascii_digit()) => {
+                return Err(Error::InvalidPlaceholder(clause.to_owned()));
+            }
+            (None, '?') => {
+                pos += 1;
+                count += 1;
+                sql.push_str(&format!("${pos}"));
+
+                continue;
+            }
+            _ => {}
+        }
+
+        sql.push(c);
+    }
+
+    if quote.is_some() {
+        return Err(Error::InvalidPlaceholder(clause.to_owned()));
+    }
+
+    Ok((sql, count))
+}
+
+fn is_identifier(value: &str) -> bool {
+    value.split('.').all(|part| {
+        part.chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
 #[derive(Debug, Clone)]
 pub enum Order {
     Asc,
@@ -226,6 +675,19 @@ pub struct ReadResult<N> {
     pub page_info: PageInfo,
 }
 
+impl<N> ReadResult<N> {
+    pub fn cursors(&self) -> Vec<&Cursor> {
+        self.edges.iter().map(|e| &e.cursor).collect()
+    }
+
+    pub fn boundary_cursors(&self) -> (Option<&Cursor>, Option<&Cursor>) {
+        (
+            self.edges.first().map(|e| &e.cursor),
+            self.edges.last().map(|e| &e.cursor),
+        )
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Args {
     pub first: Option<u16>,
@@ -237,7 +699,12 @@ pub struct Args {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Event, Writer};
+    use crate::{Event, EventCursor, Json, Writer};
+    use base64::{
+        alphabet,
+        engine::{general_purpose, GeneralPurpose},
+        Engine,
+    };
     use fake::{
         faker::{
             internet::en::{SafeEmail, Username},
@@ -491,6 +958,646 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn then_order_by() {
+        let pool = init_data("then_order_by").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        let mut reader = all_reader()
+            .then_order_by("aggregate", Order::Desc)
+            .unwrap()
+            .forward(10, Some(events[0].cursor.to_owned()));
+        reader.build();
+
+        assert_eq!(
+            reader.qb.sql(),
+            "SELECT * FROM event WHERE timestamp > $1 OR (timestamp = $1 AND version > $2 OR (version = $2 AND id > $3)) ORDER BY timestamp ASC, version ASC, id ASC, aggregate DESC LIMIT 11"
+        );
+
+        for _ in 0..100 {
+            let events = events.clone();
+            let (limit, cursor, pos) = get_random_event(&events);
+            let edges = events
+                .into_iter()
+                .skip(pos + 1)
+                .take(limit as usize + 1)
+                .collect::<Vec<_>>();
+
+            let result = all_reader()
+                .then_order_by("aggregate", Order::Desc)
+                .unwrap()
+                .forward(limit, cursor)
+                .read(&pool.to_owned())
+                .await
+                .unwrap();
+
+            test_result(result, edges, false);
+        }
+
+        assert!(all_reader()
+            .then_order_by("aggregate; DROP TABLE event", Order::Asc)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn raw_where() {
+        let pool = init_data("raw_where").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        let mut reader = all_reader()
+            .raw_where("aggregate = ? AND version > ? AND name != 'why?'")
+            .unwrap()
+            .bind("user/1")
+            .unwrap()
+            .bind(0)
+            .unwrap()
+            .forward(10, Some(events[0].cursor.to_owned()));
+        reader.build();
+
+        assert_eq!(
+            reader.qb.sql(),
+            "SELECT * FROM event WHERE (aggregate = $1 AND version > $2 AND name != 'why?') AND (timestamp > $3 OR (timestamp = $3 AND version > $4 OR (version = $4 AND id > $5))) ORDER BY timestamp ASC, version ASC, id ASC LIMIT 11"
+        );
+
+        for _ in 0..100 {
+            let events = events.clone();
+            let (id, events) = get_user_events(&events).await;
+            let (limit, cursor, pos) = get_random_event(&events);
+            let edges = events
+                .into_iter()
+                .skip(pos + 1)
+                .take(limit as usize + 1)
+                .collect::<Vec<_>>();
+
+            let result = all_reader()
+                .raw_where("aggregate = ?")
+                .unwrap()
+                .bind(id)
+                .unwrap()
+                .forward(limit, cursor)
+                .read(&pool.to_owned())
+                .await
+                .unwrap();
+
+            test_result(result, edges, false);
+        }
+
+        let result = all_reader()
+            .raw_where("aggregate = ? AND version > ?")
+            .unwrap()
+            .bind("user/1")
+            .unwrap()
+            .read(&pool.to_owned())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BindCountMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+
+        for clause in ["aggregate = ?1", "aggregate = $1", "name = 'unterminated"] {
+            assert!(matches!(
+                all_reader().raw_where(clause),
+                Err(Error::InvalidPlaceholder(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn encode_event_cursor() {
+        let pool = init_data("encode_event_cursor").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+        let event = &events[49].node;
+
+        let cursor = Cursor::encode(&EventCursor {
+            i: event.id.to_owned(),
+            v: event.version,
+            t: event.timestamp,
+        })
+        .unwrap();
+
+        assert_eq!(cursor, events[49].cursor);
+
+        let result = all_reader()
+            .forward(10, Some(cursor))
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        test_result(
+            result,
+            events.into_iter().skip(50).take(11).collect(),
+            false,
+        );
+    }
+
+    #[tokio::test]
+    async fn compact_cursors() {
+        let pool = init_data("compact_cursors").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        let result = all_reader()
+            .compact_cursors()
+            .forward(10, Some(events[49].node.to_compact_cursor().unwrap()))
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result.edges.iter().map(|e| &e.node).collect::<Vec<_>>(),
+            events[50..60].iter().map(|e| &e.node).collect::<Vec<_>>()
+        );
+
+        for (edge, expected) in result.edges.iter().zip(&events[50..60]) {
+            assert!(edge.cursor.0.len() < expected.cursor.0.len());
+
+            let cursor = edge.cursor.decode::<EventCursor>().unwrap();
+            let expected_cursor = expected.cursor.decode::<EventCursor>().unwrap();
+
+            assert_eq!(
+                (cursor.i, cursor.v, cursor.t),
+                (expected_cursor.i, expected_cursor.v, expected_cursor.t)
+            );
+        }
+
+        let result = all_reader()
+            .forward(10, result.page_info.end_cursor)
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        test_result(
+            result,
+            events.into_iter().skip(60).take(11).collect(),
+            false,
+        );
+    }
+
+    #[tokio::test]
+    async fn latest_per() {
+        let pool = init_data("latest_per").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        let mut latest: Vec<Edge<Event>> = vec![];
+        for edge in events.into_iter().rev() {
+            if !latest
+                .iter()
+                .any(|e| e.node.aggregate == edge.node.aggregate)
+            {
+                latest.push(edge);
+            }
+        }
+        latest.reverse();
+
+        let first = all_reader()
+            .latest_per("aggregate")
+            .unwrap()
+            .forward(3, None)
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        let rest = all_reader()
+            .latest_per("aggregate")
+            .unwrap()
+            .forward(40, first.page_info.end_cursor.to_owned())
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert!(first.page_info.has_next_page);
+        assert!(!rest.page_info.has_next_page);
+        assert_eq!(
+            first
+                .edges
+                .into_iter()
+                .chain(rest.edges.into_iter())
+                .collect::<Vec<_>>(),
+            latest
+        );
+    }
+
+    #[tokio::test]
+    async fn cursors() {
+        let pool = init_data("cursors").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        let result = all_reader()
+            .forward(10, Some(events[4].cursor.to_owned()))
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result.cursors(),
+            events[5..15].iter().map(|e| &e.cursor).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            result.boundary_cursors(),
+            (Some(&events[5].cursor), Some(&events[14].cursor))
+        );
+        assert_eq!(
+            result.boundary_cursors().1,
+            result.page_info.end_cursor.as_ref()
+        );
+
+        let result = all_reader()
+            .forward(10, events.last().map(|e| e.cursor.to_owned()))
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert!(result.cursors().is_empty());
+        assert_eq!(result.boundary_cursors(), (None, None));
+    }
+
+    #[tokio::test]
+    async fn read_with_count() {
+        let pool = init_data("read_with_count").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        for _ in 0..100 {
+            let events = events.clone();
+            let (id, events) = get_user_events(&events).await;
+            let total = events.len() as i64;
+            let (limit, cursor, pos) = get_random_event(&events);
+            let edges = events
+                .into_iter()
+                .skip(pos + 1)
+                .take(limit as usize + 1)
+                .collect::<Vec<_>>();
+
+            let (result, count) = aggregate_reader(id)
+                .forward(limit, cursor)
+                .read_with_count(&pool.to_owned())
+                .await
+                .unwrap();
+
+            assert_eq!(count, total);
+            test_result(result, edges, false);
+        }
+
+        let last = events.last().map(|e| e.cursor.to_owned());
+        let (result, count) = all_reader()
+            .forward(10, last)
+            .read_with_count(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(count, 100);
+        assert!(result.edges.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cursor_format_mismatch() {
+        let pool = init_data("cursor_format_mismatch").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+        let event = &events[0].node;
+
+        let mut cbor_encoded = vec![];
+        ciborium::into_writer(
+            &EventCursor {
+                i: event.id.to_owned(),
+                v: event.version,
+                t: event.timestamp,
+            },
+            &mut cbor_encoded,
+        )
+        .unwrap();
+        let engine = GeneralPurpose::new(&alphabet::URL_SAFE, general_purpose::PAD);
+        let cursor = Cursor(engine.encode(cbor_encoded));
+
+        let err = all_reader()
+            .forward(10, Some(cursor))
+            .read(&pool.to_owned())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::Cursor(crate::cursor::Error::CursorFormatMismatch {
+                expected: &[1, 2],
+                found: Some(0xa3)
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn before_one() {
+        let pool = init_data("before_one").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        for _ in 0..100 {
+            let (_, cursor, pos) = get_random_event(&events);
+
+            let node = all_reader()
+                .before_one(cursor.unwrap(), &pool.to_owned())
+                .await
+                .unwrap();
+
+            assert_eq!(node, pos.checked_sub(1).map(|p| events[p].node.to_owned()));
+        }
+
+        let node = all_reader()
+            .before_one(events[0].cursor.to_owned(), &pool.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(node, None);
+
+        let (id, events) = get_user_events(&events).await;
+        let node = aggregate_reader(id)
+            .desc()
+            .before_one(events[0].cursor.to_owned(), &pool.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(node, events.get(1).map(|e| e.node.to_owned()));
+    }
+
+    #[tokio::test]
+    async fn page() {
+        let pool = init_data("page").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        for _ in 0..10 {
+            let (limit, cursor, _) = get_random_event(&events);
+
+            let result = all_reader()
+                .page(Args {
+                    first: Some(limit),
+                    after: cursor.to_owned(),
+                    ..Default::default()
+                })
+                .unwrap()
+                .read(&pool.to_owned())
+                .await
+                .unwrap();
+
+            let expected = all_reader()
+                .forward(limit, cursor.to_owned())
+                .read(&pool.to_owned())
+                .await
+                .unwrap();
+
+            assert_eq!(result, expected);
+
+            let result = all_reader()
+                .page(Args {
+                    last: Some(limit),
+                    before: cursor.to_owned(),
+                    ..Default::default()
+                })
+                .unwrap()
+                .read(&pool.to_owned())
+                .await
+                .unwrap();
+
+            let expected = all_reader()
+                .backward(limit, cursor)
+                .read(&pool.to_owned())
+                .await
+                .unwrap();
+
+            assert_eq!(result, expected);
+        }
+
+        let result = all_reader().page(Args {
+            first: Some(10),
+            before: Some(events[0].cursor.to_owned()),
+            ..Default::default()
+        });
+
+        assert!(matches!(result, Err(Error::AmbiguousArgs)));
+
+        let result = all_reader().page(Args {
+            after: Some(events[0].cursor.to_owned()),
+            last: Some(10),
+            ..Default::default()
+        });
+
+        assert!(matches!(result, Err(Error::AmbiguousArgs)));
+    }
+
+    #[tokio::test]
+    async fn explain() {
+        let pool = init_data("explain").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+        let (id, events) = get_user_events(&events).await;
+
+        let mut reader = aggregate_reader(id).forward(10, Some(events[0].cursor.to_owned()));
+
+        assert_eq!(
+            reader.explain(),
+            (
+                "SELECT * FROM event WHERE aggregate = $1 AND (timestamp > $2 OR (timestamp = $2 AND version > $3 OR (version = $3 AND id > $4))) ORDER BY timestamp ASC, version ASC, id ASC LIMIT 11".to_owned(),
+                4
+            )
+        );
+
+        let result = reader.read(&pool.to_owned()).await.unwrap();
+
+        assert_eq!(result.edges, events[1..events.len().min(11)]);
+
+        let mut reader = all_reader().desc().backward(5, None);
+
+        assert_eq!(
+            reader.explain(),
+            (
+                "SELECT * FROM event ORDER BY timestamp ASC, version ASC, id ASC LIMIT 6"
+                    .to_owned(),
+                0
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn select_json() {
+        let pool = init_data("select_json").await.to_owned();
+        get_events(&pool, Order::Asc).await;
+
+        let mut writer = Writer::new("user/json").codec::<Json>();
+        for i in 0..8 {
+            writer = writer
+                .event(&UsermameChanged {
+                    username: format!("user_{i}"),
+                })
+                .unwrap();
+        }
+        writer.write(&pool).await.unwrap();
+
+        let result = all_reader()
+            .select_json("$.username")
+            .unwrap()
+            .forward(5, None)
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert!(result.page_info.has_next_page);
+        assert_eq!(
+            result
+                .edges
+                .iter()
+                .map(|e| (e.node.node.version, e.node.value.to_owned()))
+                .collect::<Vec<_>>(),
+            (1..6)
+                .map(|v| (v, serde_json::json!(format!("user_{}", v - 1))))
+                .collect::<Vec<_>>()
+        );
+
+        let result = all_reader()
+            .select_json("$.username")
+            .unwrap()
+            .forward(5, result.page_info.end_cursor)
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert!(!result.page_info.has_next_page);
+        assert_eq!(
+            result
+                .edges
+                .iter()
+                .map(|e| e.node.value.to_owned())
+                .collect::<Vec<_>>(),
+            vec![
+                serde_json::json!("user_5"),
+                serde_json::json!("user_6"),
+                serde_json::json!("user_7")
+            ]
+        );
+
+        let result = all_reader()
+            .select_json("$.missing")
+            .unwrap()
+            .forward(1, None)
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(result.edges[0].node.value, serde_json::Value::Null);
+
+        let result = SqliteReader::<Event>::new("SELECT * FROM event WHERE aggregate = $1")
+            .bind("user/json")
+            .unwrap()
+            .select_json("$.username")
+            .unwrap()
+            .forward(1, None)
+            .read(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(result.edges[0].node.value, serde_json::json!("user_0"));
+
+        let result = SqliteReader::<Event>::new("SELECT * FROM event WHERE aggregate = $1")
+            .select_json("$.username")
+            .unwrap()
+            .bind("user/json")
+            .unwrap()
+            .forward(1, None)
+            .read(&pool.to_owned())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BindCountMismatch {
+                expected: 1,
+                found: 2
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn read_with_neighbors() {
+        let pool = init_data("read_with_neighbors").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+
+        let result = all_reader()
+            .forward(10, Some(events[49].cursor.to_owned()))
+            .read_with_neighbors(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert!(result.page_info.has_previous_page);
+        assert!(result.page_info.has_next_page);
+        assert_eq!(
+            result.page_info.start_cursor,
+            Some(events[50].cursor.to_owned())
+        );
+        assert_eq!(
+            result.page_info.end_cursor,
+            Some(events[59].cursor.to_owned())
+        );
+        assert_eq!(
+            result.edges,
+            events.iter().skip(50).take(10).cloned().collect::<Vec<_>>()
+        );
+
+        let result = all_reader()
+            .backward(10, Some(events[49].cursor.to_owned()))
+            .read_with_neighbors(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert!(result.page_info.has_previous_page);
+        assert!(result.page_info.has_next_page);
+        assert_eq!(
+            result.edges,
+            events.iter().skip(39).take(10).cloned().collect::<Vec<_>>()
+        );
+
+        let result = all_reader()
+            .forward(10, None)
+            .read_with_neighbors(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert!(!result.page_info.has_previous_page);
+        assert!(result.page_info.has_next_page);
+
+        let result = all_reader()
+            .forward(10, Some(events[89].cursor.to_owned()))
+            .read_with_neighbors(&pool.to_owned())
+            .await
+            .unwrap();
+
+        assert!(result.page_info.has_previous_page);
+        assert!(!result.page_info.has_next_page);
+    }
+
+    #[tokio::test]
+    async fn count_distinct() {
+        let pool = init_data("count_distinct").await.to_owned();
+        let events = get_events(&pool, Order::Asc).await;
+        let name = std::any::type_name::<DisplayNameChanged>();
+
+        let mut aggregates = events
+            .iter()
+            .filter(|e| e.node.name == name)
+            .map(|e| e.node.aggregate.to_owned())
+            .collect::<Vec<_>>();
+        aggregates.sort();
+        aggregates.dedup();
+
+        assert!(!aggregates.is_empty());
+
+        let count = all_reader()
+            .raw_where("name = ?")
+            .unwrap()
+            .bind(name)
+            .unwrap()
+            .forward(1, Some(events[50].cursor.to_owned()))
+            .count_distinct("aggregate", &pool.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(count, aggregates.len() as i64);
+    }
+
     #[derive(Debug, PartialEq, Deserialize, Serialize, Dummy)]
     struct UsermameChanged {
         #[dummy(faker = "Username()")]
@@ -0,0 +1,381 @@
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use std::collections::BTreeMap;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS event (
+    id  TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    aggregate TEXT NOT NULL,
+    stream TEXT NOT NULL DEFAULT '',
+    version INTEGER NOT NULL,
+    schema_version INTEGER NOT NULL DEFAULT 1,
+    data BLOB NOT NULL,
+    metadata BLOB NULL,
+    content_type TEXT NOT NULL DEFAULT 'application/cbor',
+    timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS event_data (
+    id  TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    metadata BLOB NULL
+);
+"#;
+
+const INDEXES: &str = r#"
+DROP INDEX IF EXISTS idx_event_aggregate_version;
+CREATE INDEX IF NOT EXISTS idx_event_aggregate ON event(aggregate);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_event_aggregate_stream_version ON event(aggregate,stream,version);
+"#;
+
+const TABLES: &[&str] = &["event", "event_data"];
+
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    (
+        "event",
+        "content_type",
+        "TEXT NOT NULL DEFAULT 'application/cbor'",
+    ),
+    ("event", "stream", "TEXT NOT NULL DEFAULT ''"),
+    ("event", "schema_version", "INTEGER NOT NULL DEFAULT 1"),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Drift {
+    MissingTable(String),
+    MissingColumn {
+        table: String,
+        column: String,
+    },
+    ExtraColumn {
+        table: String,
+        column: String,
+    },
+    ColumnDefinition {
+        table: String,
+        column: String,
+        expected: String,
+        found: String,
+    },
+    MissingIndex {
+        table: String,
+        index: String,
+    },
+    ExtraIndex {
+        table: String,
+        index: String,
+    },
+    IndexDefinition {
+        table: String,
+        index: String,
+        expected: String,
+        found: String,
+    },
+}
+
+pub async fn apply(executor: &SqlitePool) -> Result<(), sqlx::Error> {
+    let mut tx = executor.begin().await?;
+    sqlx::raw_sql(SCHEMA).execute(&mut *tx).await?;
+
+    for (table, column, definition) in ADDED_COLUMNS {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info($1) WHERE name = $2)",
+        )
+        .bind(table)
+        .bind(column)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if !exists {
+            sqlx::raw_sql(&format!(
+                "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    sqlx::raw_sql(INDEXES).execute(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn check(executor: &SqlitePool) -> Result<Vec<Drift>, sqlx::Error> {
+    let mut expected_conn = SqliteConnection::connect("sqlite::memory:").await?;
+    sqlx::raw_sql(SCHEMA).execute(&mut expected_conn).await?;
+    sqlx::raw_sql(INDEXES).execute(&mut expected_conn).await?;
+
+    let mut conn = executor.acquire().await?;
+    let mut drifts = vec![];
+
+    for table in TABLES {
+        let expected = introspect(&mut expected_conn, table).await?;
+        let found = introspect(&mut conn, table).await?;
+
+        if found.columns.is_empty() {
+            drifts.push(Drift::MissingTable(table.to_string()));
+            continue;
+        }
+
+        for (column, expected) in expected.columns.iter() {
+            match found.columns.get(column) {
+                Some(found) if found != expected => drifts.push(Drift::ColumnDefinition {
+                    table: table.to_string(),
+                    column: column.to_owned(),
+                    expected: expected.to_owned(),
+                    found: found.to_owned(),
+                }),
+                Some(_) => {}
+                None => drifts.push(Drift::MissingColumn {
+                    table: table.to_string(),
+                    column: column.to_owned(),
+                }),
+            }
+        }
+
+        for column in found.columns.keys() {
+            if !expected.columns.contains_key(column) {
+                drifts.push(Drift::ExtraColumn {
+                    table: table.to_string(),
+                    column: column.to_owned(),
+                });
+            }
+        }
+
+        for (index, expected) in expected.indexes.iter() {
+            match found.indexes.get(index) {
+                Some(found) if found != expected => drifts.push(Drift::IndexDefinition {
+                    table: table.to_string(),
+                    index: index.to_owned(),
+                    expected: expected.to_owned(),
+                    found: found.to_owned(),
+                }),
+                Some(_) => {}
+                None => drifts.push(Drift::MissingIndex {
+                    table: table.to_string(),
+                    index: index.to_owned(),
+                }),
+            }
+        }
+
+        for index in found.indexes.keys() {
+            if !expected.indexes.contains_key(index) {
+                drifts.push(Drift::ExtraIndex {
+                    table: table.to_string(),
+                    index: index.to_owned(),
+                });
+            }
+        }
+    }
+
+    Ok(drifts)
+}
+
+struct TableInfo {
+    columns: BTreeMap<String, String>,
+    indexes: BTreeMap<String, String>,
+}
+
+async fn introspect(conn: &mut SqliteConnection, table: &str) -> Result<TableInfo, sqlx::Error> {
+    let columns = sqlx::query_as::<_, (String, String, bool)>(
+        r#"SELECT name, type, "notnull" FROM pragma_table_info($1) ORDER BY cid"#,
+    )
+    .bind(table)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|(name, column_type, not_null)| {
+        let definition = if not_null {
+            format!("{column_type} NOT NULL")
+        } else {
+            column_type
+        };
+
+        (name, definition)
+    })
+    .collect();
+
+    let rows = sqlx::query_as::<_, (String, bool, String)>(
+        r#"SELECT il.name, il."unique", ii.name FROM pragma_index_list($1) AS il, pragma_index_info(il.name) AS ii WHERE il.origin = 'c' ORDER BY il.name, ii.seqno"#,
+    )
+    .bind(table)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut indexes = BTreeMap::<String, String>::new();
+    for (index, unique, column) in rows {
+        indexes
+            .entry(index)
+            .and_modify(|definition| {
+                definition.pop();
+                definition.push_str(&format!(",{column})"));
+            })
+            .or_insert_with(|| {
+                let unique = if unique { "UNIQUE " } else { "" };
+                format!("{unique}({column})")
+            });
+    }
+
+    Ok(TableInfo { columns, indexes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{any::install_default_drivers, migrate::MigrateDatabase, Any};
+
+    #[tokio::test]
+    async fn apply_twice() {
+        let pool = get_pool("apply_twice").await;
+
+        apply(&pool).await.unwrap();
+        apply(&pool).await.unwrap();
+
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM event")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn apply_after_migrations() {
+        let pool = get_pool("apply_after_migrations").await;
+
+        sqlx::migrate!("../migrations").run(&pool).await.unwrap();
+        apply(&pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn apply_over_init() {
+        let pool = get_pool("apply_over_init").await;
+
+        sqlx::raw_sql(include_str!("../../migrations/20241130221817_init.sql"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO event (id, name, aggregate, version, data) VALUES ('01JDZ7C5S0V3BE2E8X7DQPBHNT', 'created', 'product/1', 1, x'f6')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        apply(&pool).await.unwrap();
+        apply(&pool).await.unwrap();
+
+        let event = sqlx::query_as::<_, crate::Event>("SELECT * FROM event")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            (
+                event.stream.as_str(),
+                event.schema_version,
+                event.content_type.as_str()
+            ),
+            ("", 1, "application/cbor")
+        );
+        assert_eq!(check(&pool).await.unwrap(), vec![]);
+
+        crate::Writer::new("product/1")
+            .stream("billing")
+            .event(&true)
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_migrations() {
+        let pool = get_pool("check_migrations").await;
+
+        sqlx::migrate!("../migrations").run(&pool).await.unwrap();
+
+        assert_eq!(check(&pool).await.unwrap(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn check_drift() {
+        let pool = get_pool("check_drift").await;
+
+        assert_eq!(
+            check(&pool).await.unwrap(),
+            vec![
+                Drift::MissingTable("event".to_owned()),
+                Drift::MissingTable("event_data".to_owned()),
+            ]
+        );
+
+        sqlx::raw_sql(
+            r#"
+CREATE TABLE event (
+    id  TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    aggregate TEXT NOT NULL,
+    stream TEXT NOT NULL DEFAULT '',
+    version TEXT NOT NULL,
+    schema_version INTEGER NOT NULL DEFAULT 1,
+    data BLOB NOT NULL,
+    metadata BLOB NULL,
+    timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    tenant TEXT NULL
+);
+
+CREATE INDEX idx_event_aggregate_stream_version ON event(aggregate,version);
+CREATE INDEX idx_event_name ON event(name);
+"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            check(&pool).await.unwrap(),
+            vec![
+                Drift::MissingColumn {
+                    table: "event".to_owned(),
+                    column: "content_type".to_owned(),
+                },
+                Drift::ColumnDefinition {
+                    table: "event".to_owned(),
+                    column: "version".to_owned(),
+                    expected: "INTEGER NOT NULL".to_owned(),
+                    found: "TEXT NOT NULL".to_owned(),
+                },
+                Drift::ExtraColumn {
+                    table: "event".to_owned(),
+                    column: "tenant".to_owned(),
+                },
+                Drift::MissingIndex {
+                    table: "event".to_owned(),
+                    index: "idx_event_aggregate".to_owned(),
+                },
+                Drift::IndexDefinition {
+                    table: "event".to_owned(),
+                    index: "idx_event_aggregate_stream_version".to_owned(),
+                    expected: "UNIQUE (aggregate,stream,version)".to_owned(),
+                    found: "(aggregate,version)".to_owned(),
+                },
+                Drift::ExtraIndex {
+                    table: "event".to_owned(),
+                    index: "idx_event_name".to_owned(),
+                },
+                Drift::MissingTable("event_data".to_owned()),
+            ]
+        );
+    }
+
+    async fn get_pool(key: impl Into<String>) -> SqlitePool {
+        let key = key.into();
+        let dsn = format!("sqlite:../target/schema_{key}.db");
+
+        install_default_drivers();
+        let _ = Any::drop_database(&dsn).await;
+        Any::create_database(&dsn).await.unwrap();
+
+        SqlitePool::connect(&dsn).await.unwrap()
+    }
+}
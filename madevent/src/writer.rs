@@ -1,13 +1,29 @@
+use crate::{event::EVENT_SOURCE, Cbor, Codec, CodecError, Event, Json};
 use serde::Serialize;
-use sqlx::{QueryBuilder, SqlitePool};
-use std::any::type_name;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool, Transaction};
+use std::{any::type_name, marker::PhantomData};
 use thiserror::Error;
 use ulid::Ulid;
 
-pub struct Writer {
+pub struct Writer<C = Cbor> {
     aggregate: String,
+    stream: String,
     original_version: u16,
-    events: Vec<(String, Vec<u8>, Option<Vec<u8>>)>,
+    max_event_bytes: Option<usize>,
+    validate_version: bool,
+    fetch_conflicts: bool,
+    split_payload: bool,
+    created_marker: Option<PendingEvent>,
+    events: Vec<PendingEvent>,
+    phantom_c: PhantomData<C>,
+}
+
+struct PendingEvent {
+    name: String,
+    schema_version: u16,
+    content_type: &'static str,
+    data: Vec<u8>,
+    metadata: Option<Vec<u8>>,
 }
 
 impl Writer {
@@ -16,10 +32,40 @@ impl Writer {
 
         Self {
             aggregate,
+            stream: Default::default(),
             events: vec![],
             original_version: 0,
+            max_event_bytes: None,
+            validate_version: false,
+            fetch_conflicts: false,
+            split_payload: false,
+            created_marker: None,
+            phantom_c: PhantomData,
         }
     }
+}
+
+impl<C: Codec> Writer<C> {
+    pub fn codec<C2: Codec>(self) -> Writer<C2> {
+        Writer {
+            aggregate: self.aggregate,
+            stream: self.stream,
+            events: self.events,
+            original_version: self.original_version,
+            max_event_bytes: self.max_event_bytes,
+            validate_version: self.validate_version,
+            fetch_conflicts: self.fetch_conflicts,
+            split_payload: self.split_payload,
+            created_marker: self.created_marker,
+            phantom_c: PhantomData,
+        }
+    }
+
+    pub fn stream(mut self, stream: impl Into<String>) -> Self {
+        self.stream = stream.into();
+
+        self
+    }
 
     pub fn original_version(mut self, original_version: u16) -> Self {
         self.original_version = original_version;
@@ -27,83 +73,246 @@ impl Writer {
         self
     }
 
-    pub fn event<D>(
-        self,
-        data: &D,
-    ) -> std::result::Result<Self, ciborium::ser::Error<std::io::Error>>
+    pub fn max_event_bytes(mut self, limit: usize) -> Self {
+        self.max_event_bytes = Some(limit);
+
+        self
+    }
+
+    pub fn validate_version(mut self) -> Self {
+        self.validate_version = true;
+
+        self
+    }
+
+    pub fn fetch_conflicts(mut self) -> Self {
+        self.fetch_conflicts = true;
+
+        self
+    }
+
+    pub fn split_payload(mut self) -> Self {
+        self.split_payload = true;
+
+        self
+    }
+
+    pub fn emit_created_marker(mut self, name: impl Into<String>) -> Result<Self> {
+        let name = name.into();
+        check_event_name(&name)?;
+
+        self.created_marker = Some(PendingEvent {
+            name,
+            schema_version: 1,
+            content_type: C::CONTENT_TYPE,
+            data: C::encode(&())?,
+            metadata: None,
+        });
+
+        Ok(self)
+    }
+
+    pub fn event<D>(self, data: &D) -> Result<Self>
     where
         D: ?Sized + Serialize,
     {
-        self.event_with_metadata_opt(data, None::<bool>.as_ref())
+        self.event_with_metadata_opt(type_name::<D>(), 1, data, None::<bool>.as_ref())
     }
 
-    pub fn event_with_metadata<D, M>(
+    pub fn event_with_metadata<D, M>(self, data: &D, metadata: &M) -> Result<Self>
+    where
+        D: ?Sized + Serialize,
+        M: ?Sized + Serialize,
+    {
+        self.event_with_metadata_opt(type_name::<D>(), 1, data, Some(metadata))
+    }
+
+    pub fn event_versioned<D>(
         self,
+        name: impl Into<String>,
+        schema_version: u16,
         data: &D,
-        metadata: &M,
-    ) -> std::result::Result<Self, ciborium::ser::Error<std::io::Error>>
+    ) -> Result<Self>
     where
         D: ?Sized + Serialize,
-        M: ?Sized + Serialize,
     {
-        self.event_with_metadata_opt(data, Some(metadata))
+        self.event_with_metadata_opt(name, schema_version, data, None::<bool>.as_ref())
+    }
+
+    pub fn event_value(
+        mut self,
+        name: impl Into<String>,
+        value: serde_json::Value,
+    ) -> Result<Self> {
+        let name = name.into();
+        check_event_name(&name)?;
+
+        self.events.push(PendingEvent {
+            name,
+            schema_version: 1,
+            content_type: Json::CONTENT_TYPE,
+            data: Json::encode(&value)?,
+            metadata: None,
+        });
+
+        Ok(self)
     }
 
     fn event_with_metadata_opt<D, M>(
         mut self,
+        name: impl Into<String>,
+        schema_version: u16,
         data: &D,
         metadata: Option<&M>,
-    ) -> std::result::Result<Self, ciborium::ser::Error<std::io::Error>>
+    ) -> Result<Self>
     where
         D: ?Sized + Serialize,
         M: ?Sized + Serialize,
     {
-        let name = type_name::<D>().to_owned();
-        let mut data_encoded = Vec::new();
-        ciborium::into_writer(data, &mut data_encoded)?;
-        let metadata_encoded = if let Some(metadata) = metadata {
-            let mut metadata_encoded = Vec::new();
-            ciborium::into_writer(metadata, &mut metadata_encoded)?;
-            Some(metadata_encoded)
+        let name = name.into();
+        check_event_name(&name)?;
+
+        let metadata = if let Some(metadata) = metadata {
+            Some(C::encode(metadata)?)
         } else {
             None
         };
 
-        self.events.push((name, data_encoded, metadata_encoded));
+        self.events.push(PendingEvent {
+            name,
+            schema_version,
+            content_type: C::CONTENT_TYPE,
+            data: C::encode(data)?,
+            metadata,
+        });
 
         Ok(self)
     }
 
     pub async fn write(&self, executor: &SqlitePool) -> Result<()> {
-        let mut version = self.original_version.to_owned();
+        if self.events.is_empty() {
+            return Ok(());
+        }
+
+        self.check_payload_size()?;
+
         let mut tx = executor.begin().await?;
+        self.insert(&mut tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn write_in_tx(&self, tx: &mut Transaction<'_, Sqlite>) -> Result<()> {
+        if self.events.is_empty() {
+            return Ok(());
+        }
+
+        self.check_payload_size()?;
+        self.insert(tx).await
+    }
+
+    fn check_payload_size(&self) -> Result<()> {
+        if let Some(limit) = self.max_event_bytes {
+            for event in self.events.iter() {
+                let size =
+                    event.data.len() + event.metadata.as_ref().map(|m| m.len()).unwrap_or_default();
+                if size > limit {
+                    return Err(WriterError::PayloadTooLarge { size, limit });
+                }
+            }
+        }
+
+        Ok(())
+    }
 
-        let mut qb =
-            QueryBuilder::new("INSERT INTO event (id, name, aggregate, version, data, metadata) ");
+    async fn insert(&self, tx: &mut Transaction<'_, Sqlite>) -> Result<()> {
+        let mut version = self.original_version.to_owned();
 
-        qb.push_values(&self.events, |mut b, (name, data, metadata)| {
+        if self.validate_version {
+            let actual = sqlx::query_scalar::<_, Option<u16>>(
+                "SELECT MAX(version) FROM event WHERE aggregate = $1 AND stream = $2",
+            )
+            .bind(&self.aggregate)
+            .bind(&self.stream)
+            .fetch_one(&mut **tx)
+            .await?
+            .unwrap_or_default();
+
+            if actual != self.original_version {
+                return Err(WriterError::VersionMismatch {
+                    expected: self.original_version,
+                    actual,
+                });
+            }
+        }
+
+        let mut qb = QueryBuilder::new(
+            "INSERT INTO event (id, name, aggregate, stream, version, schema_version, data, metadata, content_type) ",
+        );
+
+        let events = self
+            .created_marker
+            .iter()
+            .filter(|_| self.original_version == 0)
+            .chain(&self.events)
+            .map(|event| (Ulid::new().to_string(), event))
+            .collect::<Vec<_>>();
+
+        qb.push_values(&events, |mut b, (id, event)| {
             version += 1;
 
-            let id = Ulid::new().to_string();
             b.push_bind(id)
-                .push_bind(name)
+                .push_bind(&event.name)
                 .push_bind(self.aggregate.to_owned())
+                .push_bind(self.stream.to_owned())
                 .push_bind(version)
-                .push_bind(data)
-                .push_bind(metadata);
+                .push_bind(event.schema_version);
+
+            if self.split_payload {
+                b.push_bind(Vec::<u8>::new()).push_bind(None::<Vec<u8>>);
+            } else {
+                b.push_bind(&event.data).push_bind(&event.metadata);
+            }
+
+            b.push_bind(event.content_type);
         });
 
-        let Err(e) = qb.build().execute(&mut *tx).await else {
-            tx.commit().await?;
+        let Err(e) = qb.build().execute(&mut **tx).await else {
+            if self.split_payload {
+                QueryBuilder::new("INSERT INTO event_data (id, data, metadata) ")
+                    .push_values(&events, |mut b, (id, event)| {
+                        b.push_bind(id)
+                            .push_bind(&event.data)
+                            .push_bind(&event.metadata);
+                    })
+                    .build()
+                    .execute(&mut **tx)
+                    .await?;
+            }
 
             return Ok(());
         };
 
-        if e.to_string().contains("(code: 2067)") {
-            Err(WriterError::InvalidOriginalVersion)
-        } else {
-            Err(e.into())
+        if !e.to_string().contains("(code: 2067)") {
+            return Err(e.into());
         }
+
+        if !self.fetch_conflicts {
+            return Err(WriterError::InvalidOriginalVersion);
+        }
+
+        let events = sqlx::query_as::<_, Event>(&format!(
+            "SELECT * FROM {EVENT_SOURCE} WHERE aggregate = $1 AND stream = $2 AND version > $3 AND version <= $4 ORDER BY version",
+        ))
+        .bind(&self.aggregate)
+        .bind(&self.stream)
+        .bind(self.original_version)
+        .bind(version)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Err(WriterError::Conflict(events))
     }
 }
 
@@ -112,6 +321,21 @@ pub enum WriterError {
     #[error("invalid original version")]
     InvalidOriginalVersion,
 
+    #[error("conflict with {} stored events", .0.len())]
+    Conflict(Vec<Event>),
+
+    #[error("version mismatch: expected {expected}, actual {actual}")]
+    VersionMismatch { expected: u16, actual: u16 },
+
+    #[error("payload too large: {size} > {limit}")]
+    PayloadTooLarge { size: usize, limit: usize },
+
+    #[error("invalid event name: {0:?}")]
+    InvalidEventName(String),
+
+    #[error(transparent)]
+    Codec(#[from] CodecError),
+
     #[error(transparent)]
     Ciborium(#[from] ciborium::ser::Error<String>),
 
@@ -119,6 +343,16 @@ pub enum WriterError {
     Sqlx(#[from] sqlx::Error),
 }
 
+const MAX_EVENT_NAME_LEN: usize = 255;
+
+fn check_event_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_EVENT_NAME_LEN {
+        return Err(WriterError::InvalidEventName(name.to_owned()));
+    }
+
+    Ok(())
+}
+
 pub type Result<E> = std::result::Result<E, WriterError>;
 
 #[cfg(test)]
@@ -127,7 +361,10 @@ mod tests {
     use crate::Event;
     use futures::future::join_all;
     use serde::Deserialize;
-    use sqlx::{any::install_default_drivers, migrate::MigrateDatabase, Any};
+    use sqlx::{
+        any::install_default_drivers, migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Any,
+    };
+    use std::{any::type_name_of_val, collections::HashMap, time::Duration};
 
     #[tokio::test]
     async fn send() {
@@ -274,6 +511,575 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[tokio::test]
+    async fn write_empty() {
+        let pool = get_pool("sender_write_empty").await;
+
+        let res = Writer::new("product/1").write(&pool).await;
+
+        assert!(res.is_ok());
+
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM event")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn max_event_bytes() {
+        let pool = get_pool("sender_max_event_bytes").await;
+        let data = Created {
+            name: "Product 1".to_owned(),
+        };
+        let metadata = Metadata { key: 23 };
+
+        let mut size = vec![];
+        ciborium::into_writer(&data, &mut size).unwrap();
+        ciborium::into_writer(&metadata, &mut size).unwrap();
+        let size = size.len();
+
+        let err = Writer::new("product/1")
+            .max_event_bytes(size - 1)
+            .event_with_metadata(&data, &metadata)
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            WriterError::PayloadTooLarge {
+                size,
+                limit: size - 1
+            }
+            .to_string()
+        );
+
+        let res = Writer::new("product/1")
+            .max_event_bytes(size)
+            .event_with_metadata(&data, &metadata)
+            .unwrap()
+            .write(&pool)
+            .await;
+
+        assert!(res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn validate_before_begin() {
+        get_pool("sender_validate_before_begin").await;
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_millis(100))
+            .connect("sqlite:../target/writer_sender_validate_before_begin.db")
+            .await
+            .unwrap();
+        let conn = pool.acquire().await.unwrap();
+
+        let res = Writer::new("product/1")
+            .codec::<Json>()
+            .event(&HashMap::from([((1, 2), 3)]));
+
+        assert!(matches!(res, Err(WriterError::Codec(CodecError::Json(_)))));
+
+        let err = Writer::new("product/1")
+            .max_event_bytes(1)
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, WriterError::PayloadTooLarge { .. }));
+
+        let err = Writer::new("product/1")
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, WriterError::Sqlx(sqlx::Error::PoolTimedOut)));
+
+        drop(conn);
+    }
+
+    #[test]
+    fn invalid_event_name() {
+        let res = Writer::new("product/1").emit_created_marker("");
+
+        assert!(matches!(res, Err(WriterError::InvalidEventName(name)) if name.is_empty()));
+
+        let res = Writer::new("product/1").emit_created_marker("a".repeat(256));
+
+        assert!(matches!(res, Err(WriterError::InvalidEventName(_))));
+
+        let data = Nested(Nested(Nested(Nested(Nested(Nested(Nested(Nested(
+            Created {
+                name: "Product 1".to_owned(),
+            },
+        ))))))));
+        let res = Writer::new("product/1").event(&data);
+
+        assert!(matches!(
+            res,
+            Err(WriterError::InvalidEventName(name)) if name == type_name_of_val(&data)
+        ));
+
+        let res = Writer::new("product/1")
+            .emit_created_marker("StreamCreated")
+            .unwrap()
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            });
+
+        assert!(res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn validate_version() {
+        let pool = get_pool("sender_validate_version").await;
+
+        Writer::new("product/1")
+            .validate_version()
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .event(&VisibilityChanged { visible: false })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let err = Writer::new("product/1")
+            .original_version(1)
+            .validate_version()
+            .event(&Deleted { deleted: true })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            WriterError::VersionMismatch {
+                expected: 1,
+                actual: 2
+            }
+            .to_string()
+        );
+
+        let res = Writer::new("product/1")
+            .original_version(2)
+            .validate_version()
+            .event(&Deleted { deleted: true })
+            .unwrap()
+            .write(&pool)
+            .await;
+
+        assert!(res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn json_codec() {
+        let pool = get_pool("sender_json_codec").await;
+
+        Writer::new("product/1")
+            .codec::<Json>()
+            .event_with_metadata(
+                &Created {
+                    name: "Product 1".to_owned(),
+                },
+                &Metadata { key: 23 },
+            )
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let event = sqlx::query_as::<_, Event>("SELECT * FROM event")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(event.content_type, Json::CONTENT_TYPE);
+        assert_eq!(event.data, br#"{"name":"Product 1"}"#);
+        assert_eq!(
+            event.to_data::<Created>().unwrap().unwrap(),
+            Created {
+                name: "Product 1".to_owned(),
+            }
+        );
+        assert_eq!(
+            event.to_metadata::<Metadata>().unwrap().unwrap(),
+            Metadata { key: 23 }
+        );
+        assert!(event.to_data_with::<Cbor, Created>().is_err());
+    }
+
+    #[tokio::test]
+    async fn mixed_codecs() {
+        let pool = get_pool("sender_mixed_codecs").await;
+
+        Writer::new("product/1")
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .codec::<Json>()
+            .event(&VisibilityChanged { visible: false })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        Writer::new("product/1")
+            .original_version(2)
+            .event(&Deleted { deleted: true })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let events = sqlx::query_as::<_, Event>("SELECT * FROM event ORDER BY version")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| e.content_type.as_str())
+                .collect::<Vec<_>>(),
+            vec![Cbor::CONTENT_TYPE, Json::CONTENT_TYPE, Cbor::CONTENT_TYPE]
+        );
+        assert_eq!(
+            events[0].to_data::<Created>().unwrap().unwrap(),
+            Created {
+                name: "Product 1".to_owned(),
+            }
+        );
+        assert_eq!(
+            events[1].to_data::<VisibilityChanged>().unwrap().unwrap(),
+            VisibilityChanged { visible: false }
+        );
+        assert_eq!(
+            events[2].to_data::<Deleted>().unwrap().unwrap(),
+            Deleted { deleted: true }
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_conflicts() {
+        let pool = get_pool("sender_fetch_conflicts").await;
+        let mut fns = vec![];
+        for i in 0..10 {
+            let pool = pool.clone();
+            fns.push(async move {
+                Writer::new("product/1")
+                    .fetch_conflicts()
+                    .event(&Created {
+                        name: format!("Product {i}"),
+                    })
+                    .unwrap()
+                    .write(&pool)
+                    .await
+            });
+        }
+
+        let results = join_all(fns).await;
+        let stored = sqlx::query_as::<_, Event>("SELECT * FROM event")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(stored.len(), 1);
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+
+        for res in results.into_iter().filter(|r| r.is_err()) {
+            let Err(WriterError::Conflict(events)) = res else {
+                panic!("expected a conflict");
+            };
+
+            assert_eq!(events, stored);
+        }
+    }
+
+    #[tokio::test]
+    async fn write_in_tx() {
+        let pool = get_pool("sender_write_in_tx").await;
+
+        sqlx::query("CREATE TABLE product (id TEXT PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let mut tx = pool.begin().await.unwrap();
+        sqlx::query("INSERT INTO product (id, name) VALUES ('1', 'Product 1')")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        Writer::new("product/1")
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .write_in_tx(&mut tx)
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+
+        let products = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM product")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let events = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM event")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!((products, events), (0, 0));
+
+        let mut tx = pool.begin().await.unwrap();
+        sqlx::query("INSERT INTO product (id, name) VALUES ('1', 'Product 1')")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        Writer::new("product/1")
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .write_in_tx(&mut tx)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let products = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM product")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let events = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM event")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!((products, events), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn streams() {
+        let pool = get_pool("streams").await;
+
+        let writers = ["billing", "shipping"].map(|stream| {
+            Writer::new("product/1")
+                .stream(stream)
+                .event(&Created {
+                    name: format!("Product 1 {stream}"),
+                })
+                .unwrap()
+        });
+        let results = join_all(writers.iter().map(|w| w.write(&pool))).await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+
+        let err = Writer::new("product/1")
+            .stream("billing")
+            .event(&Deleted { deleted: true })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, WriterError::InvalidOriginalVersion));
+
+        Writer::new("product/1")
+            .stream("billing")
+            .original_version(1)
+            .event(&Deleted { deleted: true })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let events = sqlx::query_as::<_, (String, u16)>(
+            "SELECT stream, version FROM event WHERE aggregate = 'product/1' ORDER BY stream, version",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                ("billing".to_owned(), 1),
+                ("billing".to_owned(), 2),
+                ("shipping".to_owned(), 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn event_versioned() {
+        let pool = get_pool("event_versioned").await;
+
+        Writer::new("product/1")
+            .event_versioned(
+                "ProductRenamed",
+                1,
+                &Created {
+                    name: "Product 1".to_owned(),
+                },
+            )
+            .unwrap()
+            .event_versioned(
+                "ProductRenamed",
+                2,
+                &Edited {
+                    name: "Product 1".to_owned(),
+                    description: "".to_owned(),
+                    category: "shoes".to_owned(),
+                    visible: true,
+                    stock: 10,
+                    price: 9.99,
+                },
+            )
+            .unwrap()
+            .event(&Deleted { deleted: true })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let events = sqlx::query_as::<_, Event>(
+            "SELECT * FROM event WHERE aggregate = 'product/1' ORDER BY version",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.name.as_str(), e.schema_version))
+                .collect::<Vec<_>>(),
+            vec![
+                ("ProductRenamed", 1),
+                ("ProductRenamed", 2),
+                (type_name::<Deleted>(), 1),
+            ]
+        );
+
+        assert_eq!(
+            events[0]
+                .to_data_versioned::<Created>("ProductRenamed", 1)
+                .unwrap(),
+            Some(Created {
+                name: "Product 1".to_owned(),
+            })
+        );
+        assert_eq!(
+            events[1]
+                .to_data_versioned::<Edited>("ProductRenamed", 2)
+                .unwrap()
+                .map(|e| (e.category, e.stock)),
+            Some(("shoes".to_owned(), 10))
+        );
+        assert_eq!(
+            events[0]
+                .to_data_versioned::<Edited>("ProductRenamed", 2)
+                .unwrap(),
+            None
+        );
+        assert_eq!(events[0].to_data::<Created>().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn event_value() {
+        let pool = get_pool("event_value").await;
+        let value = serde_json::json!({
+            "name": "Product 1",
+            "tags": ["shoes", "sale"],
+            "stock": 10,
+        });
+
+        Writer::new("product/1")
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .event_value("ProductImported", value.clone())
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let events = sqlx::query_as::<_, Event>(
+            "SELECT * FROM event WHERE aggregate = 'product/1' ORDER BY version",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(events[1].name, "ProductImported");
+        assert_eq!(events[1].content_type, Json::CONTENT_TYPE);
+        assert_eq!(events[1].data_json().unwrap(), value);
+        assert_eq!(
+            events[0].data_json().unwrap(),
+            serde_json::json!({ "name": "Product 1" })
+        );
+    }
+
+    #[tokio::test]
+    async fn emit_created_marker() {
+        let pool = get_pool("emit_created_marker").await;
+
+        Writer::new("product/1")
+            .emit_created_marker("StreamCreated")
+            .unwrap()
+            .event(&Created {
+                name: "Product 1".to_owned(),
+            })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        Writer::new("product/1")
+            .original_version(2)
+            .emit_created_marker("StreamCreated")
+            .unwrap()
+            .event(&VisibilityChanged { visible: false })
+            .unwrap()
+            .write(&pool)
+            .await
+            .unwrap();
+
+        let events = sqlx::query_as::<_, (String, u16)>(
+            "SELECT name, version FROM event WHERE aggregate = 'product/1' ORDER BY version",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                ("StreamCreated".to_owned(), 1),
+                (type_name::<Created>().to_owned(), 2),
+                (type_name::<VisibilityChanged>().to_owned(), 3),
+            ]
+        );
+    }
+
     async fn get_pool(key: impl Into<String>) -> SqlitePool {
         let key = key.into();
         let dsn = format!("sqlite:../target/writer_{key}.db");
@@ -318,6 +1124,9 @@ mod tests {
         pub thumbnail: String,
     }
 
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Nested<T>(T);
+
     #[derive(Serialize, Deserialize, Debug, PartialEq)]
     struct Metadata {
         pub key: i32,
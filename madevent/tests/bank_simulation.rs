@@ -1,4 +1,4 @@
-use madevent::Event;
+use madevent::{Aggregate, Cbor, Codec, Event};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use ulid::Ulid;
@@ -10,9 +10,12 @@ async fn read() {
         id: Default::default(),
         name: Default::default(),
         aggregate: Default::default(),
+        stream: Default::default(),
+        schema_version: 1,
         version: 0,
         data: Default::default(),
         metadata: None,
+        content_type: Cbor::CONTENT_TYPE.to_owned(),
         timestamp: 0,
     });
     assert_eq!(acc.balance, 0.0);
@@ -73,8 +76,8 @@ struct Account {
 impl Account {
     fn apply(&mut self, event: Event) {
         if let Some(data) = event.to_data::<AccountCreated>().unwrap() {
-            let id = event.aggregate.split('/').last().unwrap();
-            self.id = Ulid::from_string(id).unwrap();
+            let aggregate = event.aggregate.parse::<Aggregate>().unwrap();
+            self.id = Ulid::from_string(&aggregate.id).unwrap();
             self.fullname = data.fullname;
             self.created_at = event.timestamp;
             self.updated_at = event.timestamp;
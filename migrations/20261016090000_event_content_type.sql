@@ -0,0 +1 @@
+ALTER TABLE event ADD COLUMN content_type TEXT NOT NULL DEFAULT 'application/cbor';
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS event_data (
+    id  TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    metadata BLOB NULL
+);
@@ -0,0 +1,4 @@
+ALTER TABLE event ADD COLUMN stream TEXT NOT NULL DEFAULT '';
+
+DROP INDEX idx_event_aggregate_version;
+CREATE UNIQUE INDEX idx_event_aggregate_stream_version ON event(aggregate,stream,version);
@@ -1,4 +1,4 @@
-use clap::{arg, Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::str::FromStr;
 use tracing::info;
 use tracing_subscriber::{prelude::*, EnvFilter};
@@ -11,10 +11,19 @@ struct Cli {
     #[arg(long, default_value = "error")]
     log: String,
 
+    #[arg(long, value_enum, default_value_t = LogFormat::Pretty)]
+    log_format: LogFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+enum LogFormat {
+    Json,
+    Pretty,
+}
+
 #[derive(Debug, Subcommand)]
 enum Commands {
     Serve { config: Option<String> },
@@ -29,8 +38,11 @@ async fn main() {
         std::process::exit(1);
     };
 
+    let json = args.log_format == LogFormat::Json;
+
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
+        .with(json.then(|| tracing_subscriber::fmt::layer().json()))
+        .with((!json).then(tracing_subscriber::fmt::layer))
         .with(env_filter)
         .init();
 
@@ -40,3 +52,21 @@ async fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_format() {
+        let args = Cli::try_parse_from(["cli", "serve"]).unwrap();
+
+        assert_eq!(args.log_format, LogFormat::Pretty);
+
+        let args = Cli::try_parse_from(["cli", "--log-format", "json", "serve"]).unwrap();
+
+        assert_eq!(args.log_format, LogFormat::Json);
+
+        assert!(Cli::try_parse_from(["cli", "--log-format", "xml", "serve"]).is_err());
+    }
+}